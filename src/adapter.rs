@@ -0,0 +1,374 @@
+// Receivers that transform or skip messages as they are received, keeping
+// the blocking and disconnect behaviour of the receiver they wrap. They
+// shadow the `Iterator` adaptors of the same name and iterate the same way.
+// `MappedSender` is the producer-side mirror, and `forward` pipes one channel
+// into another.
+
+use core::fmt;
+#[cfg(feature = "std")]
+use core::time::Duration;
+
+#[cfg(feature = "std")]
+use crate::{sync::Instant, RecvTimeoutError};
+use crate::{
+    sync::{self, Arc},
+    Receiver, SendError, Sender, TryRecvError, TrySendError,
+};
+
+impl<T: Send + 'static> Sender<T> {
+    // Messages go through `f` before they're queued, so a failed send can't
+    // hand the original back; the error only says why.
+    pub fn with<U, F>(&self, f: F) -> MappedSender<U>
+    where
+        F: Fn(U) -> T + Send + Sync + 'static,
+    {
+        MappedSender {
+            inner: sync::arc_dyn(alloc::sync::Arc::new(Mapped {
+                sender: self.clone(),
+                f,
+            })),
+        }
+    }
+}
+
+// Moves every message from `rx` into `tx` until the source disconnects,
+// then drops `tx` so the downstream sees the disconnect in turn. If the
+// downstream refuses a message, the source is closed and the message handed
+// back.
+pub fn forward<T>(mut rx: Receiver<T>, tx: Sender<T>) -> Result<(), SendError<T>> {
+    while let Some(t) = rx.recv() {
+        if let Err(e) = tx.send(t) {
+            rx.close();
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+pub struct MappedSender<U> {
+    inner: Arc<dyn MappedSend<U>>,
+}
+
+impl<U> Clone for MappedSender<U> {
+    fn clone(&self) -> Self {
+        MappedSender {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<U> MappedSender<U> {
+    pub fn send(&self, u: U) -> Result<(), SendError<()>> {
+        self.inner.send(u)
+    }
+
+    pub fn try_send(&self, u: U) -> Result<(), TrySendError<()>> {
+        self.inner.try_send(u)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<U> fmt::Debug for MappedSender<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MappedSender")
+            .field("closed", &self.is_closed())
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+// Erases the channel's message type along with the closure.
+trait MappedSend<U>: Send + Sync {
+    fn send(&self, u: U) -> Result<(), SendError<()>>;
+    fn try_send(&self, u: U) -> Result<(), TrySendError<()>>;
+    fn is_closed(&self) -> bool;
+    fn len(&self) -> usize;
+}
+
+struct Mapped<T, F> {
+    sender: Sender<T>,
+    f: F,
+}
+
+impl<T: Send, U, F: Fn(U) -> T + Send + Sync> MappedSend<U> for Mapped<T, F> {
+    fn send(&self, u: U) -> Result<(), SendError<()>> {
+        self.sender.send((self.f)(u)).map_err(|e| match e {
+            SendError::Full(_) => SendError::Full(()),
+            SendError::Disconnected(_) => SendError::Disconnected(()),
+            SendError::Closed(_) => SendError::Closed(()),
+        })
+    }
+
+    fn try_send(&self, u: U) -> Result<(), TrySendError<()>> {
+        self.sender.try_send((self.f)(u)).map_err(|e| match e {
+            TrySendError::Full(_) => TrySendError::Full(()),
+            TrySendError::Disconnected(_) => TrySendError::Disconnected(()),
+            TrySendError::Closed(_) => TrySendError::Closed(()),
+        })
+    }
+
+    fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    fn len(&self) -> usize {
+        self.sender.len()
+    }
+}
+
+impl<T> Receiver<T> {
+    pub fn map<U, F: FnMut(T) -> U>(self, f: F) -> Map<T, F> {
+        Map { receiver: self, f }
+    }
+
+    pub fn filter<P: FnMut(&T) -> bool>(self, predicate: P) -> Filter<T, P> {
+        Filter {
+            receiver: self,
+            predicate,
+        }
+    }
+
+    pub fn filter_map<U, F: FnMut(T) -> Option<U>>(self, f: F) -> FilterMap<T, F> {
+        FilterMap { receiver: self, f }
+    }
+}
+
+pub struct Map<T, F> {
+    receiver: Receiver<T>,
+    f: F,
+}
+
+impl<T, U, F: FnMut(T) -> U> Map<T, F> {
+    pub fn recv(&mut self) -> Option<U> {
+        self.receiver.recv().map(&mut self.f)
+    }
+
+    pub fn try_recv(&mut self) -> Result<U, TryRecvError> {
+        self.receiver.try_recv().map(&mut self.f)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<U, RecvTimeoutError> {
+        self.receiver.recv_timeout(timeout).map(&mut self.f)
+    }
+
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+
+    pub fn into_inner(self) -> Receiver<T> {
+        self.receiver
+    }
+}
+
+impl<T, U, F: FnMut(T) -> U> Iterator for Map<T, F> {
+    type Item = U;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+pub struct Filter<T, P> {
+    receiver: Receiver<T>,
+    predicate: P,
+}
+
+impl<T, P: FnMut(&T) -> bool> Filter<T, P> {
+    pub fn recv(&mut self) -> Option<T> {
+        let predicate = &mut self.predicate;
+        recv_with(&mut self.receiver, |t| predicate(&t).then_some(t))
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let predicate = &mut self.predicate;
+        try_recv_with(&mut self.receiver, |t| predicate(&t).then_some(t))
+    }
+
+    // The timeout covers the whole call, however many messages are skipped.
+    #[cfg(feature = "std")]
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let predicate = &mut self.predicate;
+        recv_timeout_with(&mut self.receiver, timeout, |t| predicate(&t).then_some(t))
+    }
+
+    pub fn into_inner(self) -> Receiver<T> {
+        self.receiver
+    }
+}
+
+impl<T, P: FnMut(&T) -> bool> Iterator for Filter<T, P> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+pub struct FilterMap<T, F> {
+    receiver: Receiver<T>,
+    f: F,
+}
+
+impl<T, U, F: FnMut(T) -> Option<U>> FilterMap<T, F> {
+    pub fn recv(&mut self) -> Option<U> {
+        recv_with(&mut self.receiver, &mut self.f)
+    }
+
+    pub fn try_recv(&mut self) -> Result<U, TryRecvError> {
+        try_recv_with(&mut self.receiver, &mut self.f)
+    }
+
+    // The timeout covers the whole call, however many messages are skipped.
+    #[cfg(feature = "std")]
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<U, RecvTimeoutError> {
+        recv_timeout_with(&mut self.receiver, timeout, &mut self.f)
+    }
+
+    pub fn into_inner(self) -> Receiver<T> {
+        self.receiver
+    }
+}
+
+impl<T, U, F: FnMut(T) -> Option<U>> Iterator for FilterMap<T, F> {
+    type Item = U;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+fn recv_with<T, U>(receiver: &mut Receiver<T>, mut f: impl FnMut(T) -> Option<U>) -> Option<U> {
+    loop {
+        if let Some(u) = f(receiver.recv()?) {
+            return Some(u);
+        }
+    }
+}
+
+fn try_recv_with<T, U>(
+    receiver: &mut Receiver<T>,
+    mut f: impl FnMut(T) -> Option<U>,
+) -> Result<U, TryRecvError> {
+    loop {
+        if let Some(u) = f(receiver.try_recv()?) {
+            return Ok(u);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn recv_timeout_with<T, U>(
+    receiver: &mut Receiver<T>,
+    timeout: Duration,
+    mut f: impl FnMut(T) -> Option<U>,
+) -> Result<U, RecvTimeoutError> {
+    let deadline = Instant::now().checked_add(timeout);
+    loop {
+        if let Some(u) = f(receiver.recv_until(deadline)?) {
+            return Ok(u);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use std::thread;
+
+    use super::*;
+    use crate::{channel, sync_channel};
+
+    #[test]
+    fn map_filter_test() {
+        let (tx, rx) = channel();
+        for i in 0..10 {
+            tx.send(i).unwrap();
+        }
+        let mut evens = rx.filter(|n: &i32| n % 2 == 0);
+        assert_eq!(evens.try_recv(), Ok(0));
+        assert_eq!(evens.recv(), Some(2));
+        let mut squares = evens.into_inner().map(|n| n * n);
+        assert_eq!(squares.try_recv(), Ok(9));
+        drop(tx);
+        assert_eq!(squares.collect::<Vec<_>>(), vec![16, 25, 36, 49, 64, 81]);
+    }
+
+    #[test]
+    fn filter_map_test() {
+        let (tx, rx) = channel::<&str>();
+        let mut numbers = rx.filter_map(|s| s.parse::<u8>().ok());
+        tx.send("x").unwrap();
+        assert_eq!(numbers.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(
+            numbers.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        let handle = thread::spawn(move || {
+            tx.send("nope").unwrap();
+            tx.send("7").unwrap();
+        });
+        assert_eq!(numbers.recv(), Some(7));
+        handle.join().unwrap();
+        assert_eq!(numbers.recv(), None);
+        assert_eq!(numbers.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Key(char),
+        Tick(u32),
+    }
+
+    #[test]
+    fn mapped_sender_test() {
+        let (tx, mut rx) = sync_channel(1);
+        let keys = tx.with(Event::Key);
+        let ticks = tx.with(Event::Tick);
+        drop(tx);
+        keys.send('a').unwrap();
+        assert_eq!(ticks.try_send(1), Err(TrySendError::Full(())));
+        let ticks2 = ticks.clone();
+        let handle = thread::spawn(move || ticks2.send(2));
+        assert_eq!(rx.recv(), Some(Event::Key('a')));
+        assert_eq!(rx.recv(), Some(Event::Tick(2)));
+        handle.join().unwrap().unwrap();
+        drop(rx);
+        assert!(keys.is_closed());
+        assert_eq!(keys.send('b'), Err(SendError::Disconnected(())));
+    }
+
+    #[test]
+    fn forward_test() {
+        let (tx, rx) = channel();
+        let (mid_tx, mid_rx) = sync_channel(1);
+        let (out_tx, out_rx) = channel();
+        let first = thread::spawn(move || forward(rx, mid_tx));
+        let second = thread::spawn(move || forward(mid_rx, out_tx));
+        for i in 0..5 {
+            tx.send(i).unwrap();
+        }
+        drop(tx);
+        assert_eq!(out_rx.collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+        first.join().unwrap().unwrap();
+        second.join().unwrap().unwrap();
+
+        let (tx, rx) = channel();
+        let (out_tx, out_rx) = channel::<u8>();
+        drop(out_rx);
+        tx.send(1).unwrap();
+        assert_eq!(forward(rx, out_tx), Err(SendError::Disconnected(1)));
+        assert!(tx.is_closed());
+    }
+}
@@ -0,0 +1,279 @@
+// A bounded channel whose storage is an inline array, so it never allocates
+// and can live in a `static`. Handles borrow the channel instead of sharing
+// ownership of it.
+
+#[cfg(feature = "std")]
+use core::time::Duration;
+
+#[cfg(feature = "std")]
+use crate::{sync::Instant, RecvTimeoutError};
+use crate::{
+    sync::{Condvar, Mutex},
+    SendError, TryRecvError, TrySendError,
+};
+
+pub struct StaticChannel<T, const N: usize> {
+    inner: Mutex<Inner<T, N>>,
+    available: Condvar,
+    space: Condvar,
+}
+
+impl<T, const N: usize> StaticChannel<T, N> {
+    pub const fn new() -> Self {
+        const { assert!(N > 0, "a static channel needs room for one message") };
+        StaticChannel {
+            inner: Mutex::new(Inner {
+                slots: [const { None }; N],
+                head: 0,
+                len: 0,
+                senders: 0,
+                receivers: 0,
+            }),
+            available: Condvar::new(),
+            space: Condvar::new(),
+        }
+    }
+
+    // Each call hands out one more sender and receiver; once every handle is
+    // dropped the other side sees a disconnect, as with the heap channels.
+    pub fn split(&self) -> (Sender<'_, T, N>, Receiver<'_, T, N>) {
+        let mut inner = self.inner.lock();
+        inner.senders += 1;
+        inner.receivers += 1;
+        drop(inner);
+        (Sender { channel: self }, Receiver { channel: self })
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl<T, const N: usize> Default for StaticChannel<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Sender<'a, T, const N: usize> {
+    channel: &'a StaticChannel<T, N>,
+}
+
+impl<T, const N: usize> Clone for Sender<'_, T, N> {
+    fn clone(&self) -> Self {
+        self.channel.inner.lock().senders += 1;
+        Sender {
+            channel: self.channel,
+        }
+    }
+}
+
+impl<T, const N: usize> Sender<'_, T, N> {
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        let mut inner = self.channel.inner.lock();
+        loop {
+            if inner.receivers == 0 {
+                return Err(SendError::Disconnected(t));
+            }
+            if inner.len < N {
+                inner.push(t);
+                drop(inner);
+                self.channel.available.notify_one();
+                return Ok(());
+            }
+            inner = self.channel.space.wait(inner);
+        }
+    }
+
+    pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        let mut inner = self.channel.inner.lock();
+        if inner.receivers == 0 {
+            return Err(TrySendError::Disconnected(t));
+        }
+        if inner.len == N {
+            return Err(TrySendError::Full(t));
+        }
+        inner.push(t);
+        drop(inner);
+        self.channel.available.notify_one();
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.channel.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channel.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl<T, const N: usize> Drop for Sender<'_, T, N> {
+    fn drop(&mut self) {
+        let mut inner = self.channel.inner.lock();
+        inner.senders -= 1;
+        let last = inner.senders == 0;
+        drop(inner);
+        if last {
+            self.channel.available.notify_all();
+        }
+    }
+}
+
+pub struct Receiver<'a, T, const N: usize> {
+    channel: &'a StaticChannel<T, N>,
+}
+
+impl<T, const N: usize> Receiver<'_, T, N> {
+    pub fn recv(&mut self) -> Option<T> {
+        let mut inner = self.channel.inner.lock();
+        loop {
+            match inner.pop() {
+                Some(t) => {
+                    drop(inner);
+                    self.channel.space.notify_one();
+                    return Some(t);
+                }
+                None if inner.senders == 0 => return None,
+                None => inner = self.channel.available.wait(inner),
+            }
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let mut inner = self.channel.inner.lock();
+        match inner.pop() {
+            Some(t) => {
+                drop(inner);
+                self.channel.space.notify_one();
+                Ok(t)
+            }
+            None if inner.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut inner = self.channel.inner.lock();
+        loop {
+            match inner.pop() {
+                Some(t) => {
+                    drop(inner);
+                    self.channel.space.notify_one();
+                    return Ok(t);
+                }
+                None if inner.senders == 0 => return Err(RecvTimeoutError::Disconnected),
+                None => {
+                    let (guard, timed_out) =
+                        crate::wait_until(&self.channel.available, inner, deadline);
+                    inner = guard;
+                    if timed_out {
+                        return Err(RecvTimeoutError::Timeout);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.channel.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channel.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl<T, const N: usize> Drop for Receiver<'_, T, N> {
+    fn drop(&mut self) {
+        let mut inner = self.channel.inner.lock();
+        inner.receivers -= 1;
+        let last = inner.receivers == 0;
+        drop(inner);
+        if last {
+            self.channel.space.notify_all();
+        }
+    }
+}
+
+impl<T, const N: usize> Iterator for Receiver<'_, T, N> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+struct Inner<T, const N: usize> {
+    slots: [Option<T>; N],
+    head: usize,
+    len: usize,
+    senders: usize,
+    receivers: usize,
+}
+
+impl<T, const N: usize> Inner<T, N> {
+    fn push(&mut self, t: T) {
+        self.slots[(self.head + self.len) % N] = Some(t);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let t = self.slots[self.head].take()?;
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        Some(t)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn static_channel_test() {
+        static CHANNEL: StaticChannel<u32, 2> = StaticChannel::new();
+        let (tx, mut rx) = CHANNEL.split();
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+        assert_eq!(rx.try_recv(), Ok(1));
+        let handle = thread::spawn(move || {
+            for i in 3..6 {
+                tx.send(i).unwrap();
+            }
+        });
+        assert_eq!(rx.by_ref().collect::<Vec<_>>(), vec![2, 3, 4, 5]);
+        handle.join().unwrap();
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn static_channel_disconnect_test() {
+        let channel = StaticChannel::<i32, 1>::default();
+        let (tx, rx) = channel.split();
+        drop(rx);
+        assert_eq!(tx.send(1), Err(SendError::Disconnected(1)));
+        assert_eq!(channel.capacity(), 1);
+        assert!(channel.is_empty());
+    }
+}
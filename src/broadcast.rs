@@ -0,0 +1,282 @@
+use std::{collections::VecDeque, error::Error, fmt};
+
+use crate::{
+    sync::{Arc, Condvar, Mutex},
+    SendError,
+};
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        let mut inner = self.shared.inner.lock();
+        inner.senders += 1;
+        drop(inner);
+        Sender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    pub fn send(&self, t: T) -> Result<usize, SendError<T>> {
+        let mut inner = self.shared.inner.lock();
+        if inner.receivers == 0 {
+            return Err(SendError::Disconnected(t));
+        }
+        if inner.ring.len() == self.shared.capacity {
+            inner.ring.pop_front();
+            inner.head += 1;
+        }
+        inner.ring.push_back(t);
+        let receivers = inner.receivers;
+        drop(inner);
+        self.shared.available.notify_all();
+        Ok(receivers)
+    }
+
+    pub fn subscribe(&self) -> Receiver<T> {
+        let mut inner = self.shared.inner.lock();
+        inner.receivers += 1;
+        let replay = self.shared.replay.min(inner.ring.len());
+        let next = inner.tail() - replay as u64;
+        drop(inner);
+        Receiver {
+            shared: Arc::clone(&self.shared),
+            next,
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut inner = self.shared.inner.lock();
+        inner.senders -= 1;
+        let last = inner.senders == 0;
+        drop(inner);
+        if last {
+            self.shared.available.notify_all();
+        }
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    next: u64,
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        let mut inner = self.shared.inner.lock();
+        inner.receivers += 1;
+        drop(inner);
+        Receiver {
+            shared: Arc::clone(&self.shared),
+            next: self.next,
+        }
+    }
+}
+
+impl<T: Clone> Receiver<T> {
+    pub fn recv(&mut self) -> Result<T, RecvError> {
+        let mut inner = self.shared.inner.lock();
+        loop {
+            match inner.take(&mut self.next) {
+                Err(TryRecvError::Empty) => {
+                    inner = self.shared.available.wait(inner);
+                }
+                Err(TryRecvError::Lagged(n)) => return Err(RecvError::Lagged(n)),
+                Err(TryRecvError::Disconnected) => return Err(RecvError::Disconnected),
+                Ok(t) => return Ok(t),
+            }
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let inner = self.shared.inner.lock();
+        inner.take(&mut self.next)
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut inner = self.shared.inner.lock();
+        inner.receivers -= 1;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvError {
+    Lagged(u64),
+    Disconnected,
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvError::Lagged(n) => write!(f, "receiver lagged behind by {} messages", n),
+            RecvError::Disconnected => "receiving on a closed channel".fmt(f),
+        }
+    }
+}
+
+impl Error for RecvError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Lagged(u64),
+    Disconnected,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => "receiving on an empty channel".fmt(f),
+            TryRecvError::Lagged(n) => write!(f, "receiver lagged behind by {} messages", n),
+            TryRecvError::Disconnected => "receiving on a closed channel".fmt(f),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
+
+struct Shared<T> {
+    inner: Mutex<Inner<T>>,
+    available: Condvar,
+    capacity: usize,
+    // How many retained messages a new subscriber starts with.
+    replay: usize,
+}
+
+struct Inner<T> {
+    ring: VecDeque<T>,
+    // Sequence number of `ring[0]`.
+    head: u64,
+    senders: usize,
+    receivers: usize,
+}
+
+impl<T> Inner<T> {
+    fn tail(&self) -> u64 {
+        self.head + self.ring.len() as u64
+    }
+}
+
+impl<T: Clone> Inner<T> {
+    fn take(&self, next: &mut u64) -> Result<T, TryRecvError> {
+        if *next < self.head {
+            // Everything between our position and the oldest retained message
+            // was overwritten; skip ahead and report how much was lost.
+            let missed = self.head - *next;
+            *next = self.head;
+            return Err(TryRecvError::Lagged(missed));
+        }
+        match self.ring.get((*next - self.head) as usize) {
+            Some(t) => {
+                *next += 1;
+                Ok(t.clone())
+            }
+            None if self.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+}
+
+pub fn channel<T: Clone>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    channel_with_replay(capacity, 0)
+}
+
+// Receivers from `subscribe` first get up to the last `replay` messages
+// before live ones.
+pub fn channel_with_replay<T: Clone>(capacity: usize, replay: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "broadcast channel capacity must be non-zero");
+    assert!(
+        replay <= capacity,
+        "broadcast channel can't replay more than its capacity"
+    );
+    let inner = Inner {
+        ring: VecDeque::with_capacity(capacity),
+        head: 0,
+        senders: 1,
+        receivers: 1,
+    };
+
+    let shared = Shared {
+        inner: Mutex::new(inner),
+        available: Condvar::new(),
+        capacity,
+        replay,
+    };
+
+    let shared = Arc::new(shared);
+
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared, next: 0 },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn every_receiver_sees_every_message_test() {
+        let (tx, mut rx1) = channel::<i32>(4);
+        let mut rx2 = tx.subscribe();
+        assert_eq!(tx.send(1), Ok(2));
+        tx.send(2).unwrap();
+        assert_eq!(rx1.recv(), Ok(1));
+        assert_eq!(rx1.recv(), Ok(2));
+        assert_eq!(rx2.recv(), Ok(1));
+        assert_eq!(rx2.recv(), Ok(2));
+        assert_eq!(rx1.try_recv(), Err(TryRecvError::Empty));
+        drop(tx);
+        assert_eq!(rx1.recv(), Err(RecvError::Disconnected));
+    }
+
+    #[test]
+    fn lagged_test() {
+        let (tx, mut rx) = channel::<i32>(2);
+        for i in 0..5 {
+            tx.send(i).unwrap();
+        }
+        assert_eq!(rx.recv(), Err(RecvError::Lagged(3)));
+        assert_eq!(rx.recv(), Ok(3));
+        assert_eq!(rx.recv(), Ok(4));
+    }
+
+    #[test]
+    fn replay_test() {
+        let (tx, _rx) = channel_with_replay::<i32>(4, 2);
+        let mut early = tx.subscribe();
+        assert_eq!(early.try_recv(), Err(TryRecvError::Empty));
+        for i in 0..5 {
+            tx.send(i).unwrap();
+        }
+        let mut late = tx.subscribe();
+        assert_eq!(late.recv(), Ok(3));
+        assert_eq!(late.recv(), Ok(4));
+        assert_eq!(late.try_recv(), Err(TryRecvError::Empty));
+        tx.send(5).unwrap();
+        assert_eq!(late.recv(), Ok(5));
+        assert_eq!(early.recv(), Err(RecvError::Lagged(2)));
+    }
+
+    #[test]
+    fn blocking_recv_test() {
+        let (tx, mut rx) = channel::<i32>(1);
+        let handle = thread::spawn(move || rx.recv());
+        thread::sleep(std::time::Duration::from_millis(10));
+        tx.send(9).unwrap();
+        assert_eq!(handle.join().unwrap(), Ok(9));
+        assert_eq!(tx.send(10), Err(SendError::Disconnected(10)));
+    }
+}
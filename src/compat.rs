@@ -0,0 +1,3 @@
+// Drop-in replacements for standard library channel APIs.
+
+pub mod mpsc;
@@ -0,0 +1,129 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    store::{self, Store},
+    RecvTimeoutError, SendError, TryRecvError,
+};
+
+pub struct Sender<K, V> {
+    inner: store::Sender<Conflated<K, V>>,
+}
+
+impl<K, V> Clone for Sender<K, V> {
+    fn clone(&self) -> Self {
+        Sender {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Sender<K, V> {
+    // A key that is still queued keeps its place in line; only its value is
+    // replaced, and the stale value is handed back.
+    pub fn send(&self, key: K, value: V) -> Result<Option<V>, SendError<(K, V)>> {
+        self.inner.send((key, value), |queue, (key, value)| {
+            if let Some(slot) = queue.values.get_mut(&key) {
+                return Some(std::mem::replace(slot, value));
+            }
+            queue.order.push_back(key.clone());
+            queue.values.insert(key, value);
+            None
+        })
+    }
+}
+
+pub struct Receiver<K, V> {
+    inner: store::Receiver<Conflated<K, V>>,
+}
+
+impl<K: Hash + Eq, V> Receiver<K, V> {
+    pub fn recv(&mut self) -> Option<(K, V)> {
+        self.inner.recv()
+    }
+
+    pub fn try_recv(&mut self) -> Result<(K, V), TryRecvError> {
+        self.inner.try_recv()
+    }
+
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<(K, V), RecvTimeoutError> {
+        self.inner.recv_timeout(timeout)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Hash + Eq, V> Iterator for Receiver<K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+struct Conflated<K, V> {
+    order: VecDeque<K>,
+    values: HashMap<K, V>,
+}
+
+impl<K: Hash + Eq, V> Store for Conflated<K, V> {
+    type Item = (K, V);
+
+    fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    fn pop(&mut self) -> Result<(K, V), Option<Instant>> {
+        let key = self.order.pop_front().ok_or(None)?;
+        let value = self.values.remove(&key).expect("queued key has a value");
+        Ok((key, value))
+    }
+}
+
+pub fn channel<K, V>() -> (Sender<K, V>, Receiver<K, V>) {
+    let (tx, rx) = store::channel(Conflated {
+        order: VecDeque::new(),
+        values: HashMap::new(),
+    });
+    (Sender { inner: tx }, Receiver { inner: rx })
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn conflate_test() {
+        let (tx, rx) = channel::<&str, u32>();
+        assert_eq!(tx.send("btc", 1), Ok(None));
+        assert_eq!(tx.send("eth", 10), Ok(None));
+        assert_eq!(tx.send("btc", 2), Ok(Some(1)));
+        assert_eq!(tx.send("btc", 3), Ok(Some(2)));
+        assert_eq!(rx.len(), 2);
+        drop(tx);
+        assert_eq!(rx.collect::<Vec<_>>(), vec![("btc", 3), ("eth", 10)]);
+    }
+
+    #[test]
+    fn conflate_requeue_test() {
+        let (tx, mut rx) = channel::<u8, u8>();
+        tx.send(1, 1).unwrap();
+        assert_eq!(rx.recv(), Some((1, 1)));
+        let handle = thread::spawn(move || {
+            tx.send(1, 2).unwrap();
+        });
+        assert_eq!(rx.recv(), Some((1, 2)));
+        handle.join().unwrap();
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    store::{self, Store},
+    RecvTimeoutError, SendError, TryRecvError,
+};
+
+pub struct Sender<T> {
+    inner: store::Sender<Schedule<T>>,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Sender {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        self.send_at(t, Instant::now())
+    }
+
+    // A delay too long to represent as an `Instant` means the message never
+    // comes due. Like any pending message it still counts in `len` and keeps
+    // the receiver waiting after the senders are gone.
+    pub fn send_after(&self, t: T, delay: Duration) -> Result<(), SendError<T>> {
+        match Instant::now().checked_add(delay) {
+            Some(at) => self.send_at(t, at),
+            None => self.inner.send(t, |schedule, t| schedule.never.push(t)),
+        }
+    }
+
+    pub fn send_at(&self, t: T, at: Instant) -> Result<(), SendError<T>> {
+        self.inner.send(t, |schedule, value| {
+            let seq = schedule.seq;
+            schedule.seq += 1;
+            schedule.heap.push(Entry { at, seq, value });
+        })
+    }
+}
+
+pub struct Receiver<T> {
+    inner: store::Receiver<Schedule<T>>,
+}
+
+impl<T> Receiver<T> {
+    pub fn recv(&mut self) -> Option<T> {
+        self.inner.recv()
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.inner.try_recv()
+    }
+
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.inner.recv_timeout(timeout)
+    }
+
+    // Counts pending messages as well as due ones.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Iterator for Receiver<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+struct Entry<T> {
+    at: Instant,
+    seq: u64,
+    value: T,
+}
+
+// Reversed so the heap yields the earliest deadline first, with ties broken
+// by send order.
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.at, other.seq).cmp(&(self.at, self.seq))
+    }
+}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+struct Schedule<T> {
+    heap: BinaryHeap<Entry<T>>,
+    never: Vec<T>,
+    seq: u64,
+}
+
+impl<T> Store for Schedule<T> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        self.heap.len() + self.never.len()
+    }
+
+    fn pop(&mut self) -> Result<T, Option<Instant>> {
+        match self.heap.peek() {
+            Some(entry) if entry.at <= Instant::now() => Ok(self.heap.pop().unwrap().value),
+            Some(entry) => Err(Some(entry.at)),
+            None => Err(None),
+        }
+    }
+}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let (tx, rx) = store::channel(Schedule {
+        heap: BinaryHeap::new(),
+        never: Vec::new(),
+        seq: 0,
+    });
+    (Sender { inner: tx }, Receiver { inner: rx })
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn send_after_test() {
+        let (tx, mut rx) = channel::<&str>();
+        let start = Instant::now();
+        tx.send_after("late", Duration::from_millis(40)).unwrap();
+        tx.send_after("early", Duration::from_millis(20)).unwrap();
+        tx.send("now").unwrap();
+        assert_eq!(rx.try_recv(), Ok("now"));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(rx.len(), 2);
+        drop(tx);
+        assert_eq!(rx.recv(), Some("early"));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!(rx.recv(), Some("late"));
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert_eq!(rx.recv(), None);
+    }
+
+    #[test]
+    fn earlier_send_wakes_receiver_test() {
+        let (tx, mut rx) = channel::<i32>();
+        tx.send_after(1, Duration::from_secs(60)).unwrap();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            tx.send(2).unwrap();
+            tx
+        });
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(2));
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        drop(handle.join().unwrap());
+    }
+
+    #[test]
+    fn never_due_test() {
+        let (tx, mut rx) = channel::<i32>();
+        tx.send_after(1, Duration::MAX).unwrap();
+        tx.send_after(2, Duration::from_secs(1 << 40)).unwrap();
+        assert_eq!(rx.len(), 2);
+        drop(tx);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Timeout)
+        );
+    }
+}
@@ -0,0 +1,244 @@
+// Channel handles with the message type erased, for code that only learns
+// the type at runtime. Values travel as `Box<dyn Any + Send>`; a value of the
+// wrong type is refused, never lost.
+
+use std::{
+    any::{self, Any, TypeId},
+    error::Error,
+    fmt,
+};
+
+use crate::{Receiver, SendError, Sender, TryRecvError};
+
+pub type AnyValue = Box<dyn Any + Send>;
+
+impl<T: Send + 'static> Sender<T> {
+    pub fn erase(self) -> AnySender {
+        AnySender {
+            inner: Box::new(self),
+            type_id: TypeId::of::<T>(),
+            type_name: any::type_name::<T>(),
+        }
+    }
+}
+
+impl<T: Send + 'static> Receiver<T> {
+    pub fn erase(self) -> AnyReceiver {
+        AnyReceiver {
+            inner: Box::new(self),
+            type_id: TypeId::of::<T>(),
+            type_name: any::type_name::<T>(),
+        }
+    }
+}
+
+pub struct AnySender {
+    inner: Box<dyn ErasedSender>,
+    type_id: TypeId,
+    type_name: &'static str,
+}
+
+impl AnySender {
+    pub fn send(&self, value: AnyValue) -> Result<(), AnySendError> {
+        self.inner.send(value)
+    }
+
+    pub fn send_as<T: Send + 'static>(&self, t: T) -> Result<(), AnySendError> {
+        self.send(Box::new(t))
+    }
+
+    pub fn accepts<T: 'static>(&self) -> bool {
+        self.type_id == TypeId::of::<T>()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+}
+
+impl fmt::Debug for AnySender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnySender")
+            .field("type", &self.type_name)
+            .finish()
+    }
+}
+
+pub struct AnyReceiver {
+    inner: Box<dyn ErasedReceiver>,
+    type_id: TypeId,
+    type_name: &'static str,
+}
+
+impl AnyReceiver {
+    pub fn recv(&mut self) -> Option<AnyValue> {
+        self.inner.recv()
+    }
+
+    pub fn try_recv(&mut self) -> Result<AnyValue, TryRecvError> {
+        self.inner.try_recv()
+    }
+
+    // Asking for the wrong type leaves the queue untouched.
+    pub fn recv_as<T: 'static>(&mut self) -> Result<T, RecvAsError> {
+        if !self.yields::<T>() {
+            return Err(RecvAsError::WrongType);
+        }
+        let value = self.recv().ok_or(RecvAsError::Disconnected)?;
+        Ok(*value.downcast().expect("type checked above"))
+    }
+
+    pub fn yields<T: 'static>(&self) -> bool {
+        self.type_id == TypeId::of::<T>()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Debug for AnyReceiver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnyReceiver")
+            .field("type", &self.type_name)
+            .finish()
+    }
+}
+
+trait ErasedSender: Send + Sync {
+    fn send(&self, value: AnyValue) -> Result<(), AnySendError>;
+    fn is_closed(&self) -> bool;
+}
+
+impl<T: Send + 'static> ErasedSender for Sender<T> {
+    fn send(&self, value: AnyValue) -> Result<(), AnySendError> {
+        let t = value.downcast::<T>().map_err(AnySendError::WrongType)?;
+        Sender::send(self, *t).map_err(|e| {
+            AnySendError::Send(match e {
+                SendError::Full(t) => SendError::Full(Box::new(t)),
+                SendError::Disconnected(t) => SendError::Disconnected(Box::new(t)),
+                SendError::Closed(t) => SendError::Closed(Box::new(t)),
+            })
+        })
+    }
+
+    fn is_closed(&self) -> bool {
+        Sender::is_closed(self)
+    }
+}
+
+trait ErasedReceiver: Send {
+    fn recv(&mut self) -> Option<AnyValue>;
+    fn try_recv(&mut self) -> Result<AnyValue, TryRecvError>;
+    fn len(&self) -> usize;
+}
+
+impl<T: Send + 'static> ErasedReceiver for Receiver<T> {
+    fn recv(&mut self) -> Option<AnyValue> {
+        Receiver::recv(self).map(|t| Box::new(t) as AnyValue)
+    }
+
+    fn try_recv(&mut self) -> Result<AnyValue, TryRecvError> {
+        Receiver::try_recv(self).map(|t| Box::new(t) as AnyValue)
+    }
+
+    fn len(&self) -> usize {
+        Receiver::len(self)
+    }
+}
+
+pub enum AnySendError {
+    // The value isn't of the channel's type and is handed back.
+    WrongType(AnyValue),
+    Send(SendError<AnyValue>),
+}
+
+impl AnySendError {
+    pub fn into_inner(self) -> AnyValue {
+        match self {
+            AnySendError::WrongType(value) => value,
+            AnySendError::Send(e) => e.into_inner(),
+        }
+    }
+}
+
+impl fmt::Debug for AnySendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnySendError::WrongType(_) => "WrongType(..)".fmt(f),
+            AnySendError::Send(e) => f.debug_tuple("Send").field(e).finish(),
+        }
+    }
+}
+
+impl fmt::Display for AnySendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnySendError::WrongType(_) => "sending a value of the wrong type".fmt(f),
+            AnySendError::Send(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for AnySendError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvAsError {
+    WrongType,
+    Disconnected,
+}
+
+impl fmt::Display for RecvAsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvAsError::WrongType => "receiving as the wrong type".fmt(f),
+            RecvAsError::Disconnected => "receiving on a closed channel".fmt(f),
+        }
+    }
+}
+
+impl Error for RecvAsError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::channel;
+
+    #[test]
+    fn erased_test() {
+        let (tx, rx) = channel::<u32>();
+        let (tx, mut rx) = (tx.erase(), rx.erase());
+        assert!(tx.accepts::<u32>() && !tx.accepts::<i32>());
+        tx.send_as(1u32).unwrap();
+        let e = tx.send_as("one").unwrap_err();
+        assert_eq!(e.to_string(), "sending a value of the wrong type");
+        assert_eq!(*e.into_inner().downcast::<&str>().unwrap(), "one");
+        tx.send(Box::new(2u32)).unwrap();
+
+        assert_eq!(rx.len(), 2);
+        assert_eq!(rx.recv_as::<String>(), Err(RecvAsError::WrongType));
+        assert_eq!(rx.recv_as::<u32>(), Ok(1));
+        assert_eq!(*rx.try_recv().unwrap().downcast::<u32>().unwrap(), 2);
+        drop(tx);
+        assert_eq!(rx.recv_as::<u32>(), Err(RecvAsError::Disconnected));
+        assert_eq!(format!("{:?}", rx), r#"AnyReceiver { type: "u32" }"#);
+    }
+
+    #[test]
+    fn erased_disconnected_test() {
+        let (tx, rx) = channel::<u8>();
+        let tx = tx.erase();
+        drop(rx);
+        assert!(tx.is_closed());
+        match tx.send_as(7u8) {
+            Err(AnySendError::Send(SendError::Disconnected(value))) => {
+                assert_eq!(*value.downcast::<u8>().unwrap(), 7)
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+// A socket that is readable while a pollable channel may have something for
+// its receivers, so they can wait on it with `poll`/`epoll` next to other
+// file descriptors. Readiness is dropped once a receive finds the queue
+// empty, so drain with `try_recv` until `Empty` before polling again.
+
+use std::{
+    io::{self, Read, Write},
+    os::{
+        fd::{AsFd, AsRawFd, BorrowedFd, RawFd},
+        unix::net::UnixStream,
+    },
+};
+
+use crate::Receiver;
+
+pub(crate) struct Signal {
+    reader: UnixStream,
+    writer: UnixStream,
+    raised: bool,
+}
+
+impl Signal {
+    pub(crate) fn new() -> io::Result<Self> {
+        let (reader, writer) = UnixStream::pair()?;
+        reader.set_nonblocking(true)?;
+        writer.set_nonblocking(true)?;
+        Ok(Signal {
+            reader,
+            writer,
+            raised: false,
+        })
+    }
+
+    // At most one byte is ever in flight, so neither call can block.
+    pub(crate) fn raise(&mut self) {
+        if !self.raised {
+            self.raised = (&self.writer).write(&[1]).is_ok();
+        }
+    }
+
+    pub(crate) fn lower(&mut self) {
+        if self.raised {
+            self.raised = (&self.reader).read(&mut [0]).is_err();
+        }
+    }
+}
+
+impl<T> AsRawFd for Receiver<T> {
+    // Panics unless the channel was built with `ChannelBuilder::pollable`.
+    fn as_raw_fd(&self) -> RawFd {
+        let inner = self.shared.inner.lock();
+        let signal = inner
+            .signal
+            .as_ref()
+            .expect("channel was not built with `pollable`");
+        signal.reader.as_raw_fd()
+    }
+}
+
+impl<T> AsFd for Receiver<T> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        // SAFETY: the signal lives as long as the channel, which outlives
+        // this receiver.
+        unsafe { BorrowedFd::borrow_raw(self.as_raw_fd()) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ChannelBuilder, TryRecvError};
+
+    fn readable<T>(rx: &Receiver<T>) -> bool {
+        let mut fd = libc::pollfd {
+            fd: rx.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `fd` is a valid pollfd for the duration of the call.
+        let n = unsafe { libc::poll(&mut fd, 1, 0) };
+        assert!(n >= 0, "poll failed");
+        fd.revents & libc::POLLIN != 0
+    }
+
+    #[test]
+    fn pollable_test() {
+        let (tx, mut rx) = ChannelBuilder::new().pollable().build();
+        assert!(!readable(&rx));
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert!(readable(&rx));
+        assert_eq!(rx.try_recv(), Ok(1));
+        assert_eq!(rx.try_recv(), Ok(2));
+        assert!(readable(&rx));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        assert!(!readable(&rx));
+
+        drop(tx);
+        assert!(readable(&rx));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+        assert!(readable(&rx));
+    }
+
+    #[test]
+    fn pollable_batch_test() {
+        let (tx, mut rx) = ChannelBuilder::new().pollable().try_build().unwrap();
+        tx.send_all(0..3).unwrap();
+        assert!(readable(&rx));
+        assert_eq!(rx.drain().len(), 3);
+        assert!(!readable(&rx));
+
+        tx.send_all(0..3).unwrap();
+        let mut buf = Vec::new();
+        assert_eq!(rx.recv_many(&mut buf, 10), 3);
+        assert!(!readable(&rx));
+    }
+}
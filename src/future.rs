@@ -0,0 +1,496 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use crate::{Inner, OverflowPolicy, Receiver, SendError, Sender};
+
+pub struct RecvFuture<'a, T> {
+    pub(crate) receiver: &'a mut Receiver<T>,
+}
+
+impl<T> Future for RecvFuture<'_, T> {
+    type Output = Option<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+pub struct SendFuture<'a, T> {
+    pub(crate) sender: &'a Sender<T>,
+    pub(crate) value: Option<T>,
+    pub(crate) handoff: Option<u64>,
+}
+
+impl<T> Future for SendFuture<'_, T> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut inner = this.sender.shared.inner.lock();
+        if let Some(handoff) = this.handoff {
+            // Rendezvous: wait until a receiver has taken our message. If the
+            // future is dropped before that, the message stays queued.
+            if inner.taken >= handoff {
+                return Poll::Ready(Ok(()));
+            }
+            if inner.receivers == 0 {
+                let t = inner.pop_newest().expect("rendezvous message was taken");
+                return Poll::Ready(Err(SendError::Disconnected(t)));
+            }
+            inner.send_waiters.register(cx.waker());
+            return Poll::Pending;
+        }
+
+        assert!(this.value.is_some(), "SendFuture polled after completion");
+        let handoff = inner.taken + 1;
+        match poll_push(this.sender, &mut inner, cx, &mut this.value) {
+            Poll::Ready(Ok(())) if inner.capacity == Some(0) => {}
+            poll => return poll,
+        }
+        this.handoff = Some(handoff);
+        inner.send_waiters.register(cx.waker());
+        Poll::Pending
+    }
+}
+
+pub struct ClosedFuture<'a, T> {
+    pub(crate) sender: &'a Sender<T>,
+}
+
+impl<T> Future for ClosedFuture<'_, T> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut inner = self.sender.shared.inner.lock();
+        if inner.refusal().is_some() {
+            return Poll::Ready(());
+        }
+        inner.send_waiters.register(cx.waker());
+        Poll::Pending
+    }
+}
+
+// Nothing in a receiver or a pending send is ever pinned in place, whatever
+// `T` is.
+impl<T> Unpin for Receiver<T> {}
+impl<T> Unpin for SendFuture<'_, T> {}
+
+#[cfg(feature = "futures")]
+impl<T> futures_core::Stream for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.get_mut().poll_recv(cx)
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<T> futures_sink::Sink<T> for Sender<T> {
+    type Error = SendError<()>;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sender::poll_ready(&self, cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, t: T) -> Result<(), Self::Error> {
+        let mut inner = self.shared.inner.lock();
+        if let Some(refusal) = inner.refusal() {
+            return Err(refusal.send(()));
+        }
+        if (inner.lacks_room(&t) || !inner.is_next(None)) && inner.policy != OverflowPolicy::Block {
+            match inner.overflow(&t) {
+                Some(true) => {}
+                Some(false) => return Ok(()),
+                None => return Err(SendError::Full(())),
+            }
+        }
+        // The slot seen by `poll_ready` isn't reserved, so racing sinks can
+        // overshoot the capacity by one message each.
+        self.shared.push(&mut inner, t);
+        if inner.defers_wake() {
+            return Ok(());
+        }
+        inner.wake_receivers();
+        drop(inner);
+        self.shared.available.notify_one();
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let mut inner = self.shared.inner.lock();
+        // Only a rendezvous channel has anything to flush: the handoff itself.
+        if inner.capacity != Some(0) || inner.queue.is_empty() {
+            return Poll::Ready(Ok(()));
+        }
+        if inner.receivers == 0 {
+            return Poll::Ready(Err(SendError::Disconnected(())));
+        }
+        inner.send_waiters.register(cx.waker());
+        Poll::Pending
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.poll_flush(cx)
+    }
+}
+
+// For event loops and selectors outside this crate. A registered waker is
+// woken once, the next time the handle may have become ready, and has to be
+// registered again after that. Check `is_ready` after registering so a
+// change in between isn't missed.
+pub trait Selectable {
+    // A receiver is ready when it holds a message or the channel has
+    // disconnected; a sender when a send wouldn't block.
+    fn is_ready(&self) -> bool;
+    fn register(&self, waker: &Waker);
+    fn unregister(&self, waker: &Waker);
+}
+
+impl<T> Selectable for Receiver<T> {
+    fn is_ready(&self) -> bool {
+        if !self.buffer.is_empty() {
+            return true;
+        }
+        let inner = self.shared.inner.lock();
+        (!inner.queue.is_empty() && !inner.paused) || inner.ends_receiving()
+    }
+
+    fn register(&self, waker: &Waker) {
+        self.shared.inner.lock().recv_waiters.register(waker);
+    }
+
+    fn unregister(&self, waker: &Waker) {
+        self.shared.inner.lock().recv_waiters.unregister(waker);
+    }
+}
+
+impl<T> Selectable for Sender<T> {
+    fn is_ready(&self) -> bool {
+        let inner = self.shared.inner.lock();
+        inner.refusal().is_some() || !inner.is_full() || inner.policy != OverflowPolicy::Block
+    }
+
+    fn register(&self, waker: &Waker) {
+        self.shared.inner.lock().send_waiters.register(waker);
+    }
+
+    fn unregister(&self, waker: &Waker) {
+        self.shared.inner.lock().send_waiters.unregister(waker);
+    }
+}
+
+// Queues the value unless it has to wait for room, in which case it's left
+// in `value` and the waker registered.
+fn poll_push<T>(
+    sender: &Sender<T>,
+    inner: &mut Inner<T>,
+    cx: &Context<'_>,
+    value: &mut Option<T>,
+) -> Poll<Result<(), SendError<T>>> {
+    let t = value.take().expect("no value to send");
+    if let Some(refusal) = inner.refusal() {
+        return Poll::Ready(Err(refusal.send(t)));
+    }
+    if inner.lacks_room(&t) || !inner.is_next(None) {
+        match inner.overflow(&t) {
+            Some(true) => {}
+            Some(false) => return Poll::Ready(Ok(())),
+            None if inner.policy == OverflowPolicy::Reject => {
+                return Poll::Ready(Err(SendError::Full(t)));
+            }
+            None => {
+                *value = Some(t);
+                inner.send_waiters.register(cx.waker());
+                return Poll::Pending;
+            }
+        }
+    }
+    sender.shared.push(inner, t);
+    if !inner.defers_wake() {
+        inner.wake_receivers();
+        sender.shared.available.notify_one();
+    }
+    Poll::Ready(Ok(()))
+}
+
+impl<T> Receiver<T> {
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        if let Some(t) = self.pop_buffered() {
+            return Poll::Ready(Some(t));
+        }
+        let mut inner = self.shared.inner.lock();
+        match self.shared.take(&mut inner, &mut self.buffer) {
+            Some(t) => Poll::Ready(Some(t)),
+            None if inner.ends_receiving() => Poll::Ready(None),
+            None => {
+                inner.recv_waiters.register(cx.waker());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    // Ready once a send would not have to wait for room. The room isn't
+    // reserved, so racing senders can still find the channel full.
+    pub fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), SendError<()>>> {
+        let mut inner = self.shared.inner.lock();
+        if let Some(refusal) = inner.refusal() {
+            return Poll::Ready(Err(refusal.send(())));
+        }
+        // Only a blocking channel ever makes a sender wait; the others settle
+        // a full queue when the message is sent. Blocked senders that got
+        // here first keep their turn.
+        if (!inner.is_full() && inner.is_next(None)) || inner.policy != OverflowPolicy::Block {
+            return Poll::Ready(Ok(()));
+        }
+        inner.send_waiters.register(cx.waker());
+        Poll::Pending
+    }
+
+    // Takes the value out of `value` once it's queued and leaves it there
+    // while the channel is full. On a rendezvous channel this doesn't wait for
+    // a receiver to take the message; `send_async` does.
+    pub fn poll_send(
+        &self,
+        cx: &mut Context<'_>,
+        value: &mut Option<T>,
+    ) -> Poll<Result<(), SendError<T>>> {
+        let mut inner = self.shared.inner.lock();
+        poll_push(self, &mut inner, cx, value)
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    use std::{
+        sync::Arc,
+        task::{Wake, Waker},
+        thread::{self, Thread},
+    };
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let mut future = std::pin::pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{thread, time::Duration};
+
+    use super::*;
+    use crate::{channel, sync_channel};
+
+    #[test]
+    fn recv_async_test() {
+        let (tx, mut rx) = channel::<i32>();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            tx.send(1).unwrap();
+            tx.send(2).unwrap();
+        });
+        assert_eq!(block_on(rx.recv_async()), Some(1));
+        assert_eq!(block_on(rx.recv_async()), Some(2));
+        handle.join().unwrap();
+        assert_eq!(block_on(rx.recv_async()), None);
+    }
+
+    #[test]
+    fn recv_async_bounded_test() {
+        let (tx, mut rx) = sync_channel::<i32>(1);
+        let handle = thread::spawn(move || {
+            for i in 0..10 {
+                tx.send(i).unwrap();
+            }
+        });
+        let mut got = Vec::new();
+        while let Some(i) = block_on(rx.recv_async()) {
+            got.push(i);
+        }
+        handle.join().unwrap();
+        assert_eq!(got, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn hybrid_test() {
+        let (tx, mut rx) = sync_channel::<i32>(1);
+        let async_tx = tx.clone();
+        let blocking = thread::spawn(move || {
+            for i in 0..5 {
+                tx.send(i).unwrap();
+            }
+        });
+        let asynchronous = thread::spawn(move || {
+            for i in 5..10 {
+                block_on(async_tx.send_async(i)).unwrap();
+            }
+        });
+        let mut got = Vec::new();
+        while got.len() < 10 {
+            // Alternate between the blocking and the async receive path.
+            let next = if got.len() % 2 == 0 {
+                rx.recv()
+            } else {
+                block_on(rx.recv_async())
+            };
+            got.push(next.unwrap());
+        }
+        blocking.join().unwrap();
+        asynchronous.join().unwrap();
+        assert_eq!(rx.recv(), None);
+        got.sort();
+        assert_eq!(got, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn send_async_rendezvous_test() {
+        let (tx, rx) = sync_channel::<i32>(0);
+        let handle = thread::spawn(move || rx.into_iter().collect::<Vec<_>>());
+        block_on(tx.send_async(1)).unwrap();
+        block_on(tx.send_async(2)).unwrap();
+        drop(tx);
+        assert_eq!(handle.join().unwrap(), vec![1, 2]);
+
+        let (tx, rx) = sync_channel::<i32>(0);
+        drop(rx);
+        assert_eq!(block_on(tx.send_async(3)), Err(SendError::Disconnected(3)));
+    }
+
+    #[test]
+    fn closed_test() {
+        let (tx, rx) = channel::<i32>();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            drop(rx);
+        });
+        block_on(tx.closed());
+        assert!(tx.is_closed());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn poll_api_test() {
+        use std::future::poll_fn;
+
+        let (tx, mut rx) = sync_channel::<i32>(1);
+        let handle = thread::spawn(move || {
+            for i in 0..3 {
+                let mut value = Some(i);
+                block_on(poll_fn(|cx| tx.poll_send(cx, &mut value))).unwrap();
+                assert!(value.is_none());
+            }
+            block_on(poll_fn(|cx| tx.poll_ready(cx))).unwrap();
+            tx
+        });
+        let got: Vec<_> = (0..3)
+            .map(|_| block_on(poll_fn(|cx| rx.poll_recv(cx))))
+            .collect();
+        assert_eq!(got, vec![Some(0), Some(1), Some(2)]);
+        let tx = handle.join().unwrap();
+        let mut value = Some(3);
+        drop(rx);
+        let sent = block_on(poll_fn(|cx| tx.poll_send(cx, &mut value)));
+        assert_eq!(sent, Err(SendError::Disconnected(3)));
+        let ready = block_on(poll_fn(|cx| tx.poll_ready(cx)));
+        assert_eq!(ready, Err(SendError::Disconnected(())));
+    }
+
+    #[test]
+    fn selectable_test() {
+        use std::{
+            sync::{
+                atomic::{AtomicUsize, Ordering},
+                Arc,
+            },
+            task::Wake,
+        };
+
+        struct CountingWaker(AtomicUsize);
+
+        impl Wake for CountingWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let count = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(Arc::clone(&count));
+        let woken = || count.0.load(Ordering::Relaxed);
+
+        let (tx, mut rx) = sync_channel::<i32>(1);
+        assert!(!rx.is_ready() && tx.is_ready());
+        rx.register(&waker);
+        tx.send(1).unwrap();
+        assert_eq!(woken(), 1);
+        assert!(rx.is_ready() && !tx.is_ready());
+
+        tx.register(&waker);
+        tx.unregister(&waker);
+        rx.recv().unwrap();
+        assert_eq!(woken(), 1);
+        assert!(tx.is_ready());
+
+        rx.register(&waker);
+        drop(tx);
+        assert_eq!(woken(), 2);
+        assert!(rx.is_ready());
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn stream_test() {
+        use futures_core::Stream;
+
+        let (tx, mut rx) = channel::<i32>();
+        let handle = thread::spawn(move || {
+            for i in 0..3 {
+                tx.send(i).unwrap();
+            }
+        });
+        let mut next = || block_on(std::future::poll_fn(|cx| Pin::new(&mut rx).poll_next(cx)));
+        assert_eq!(next(), Some(0));
+        assert_eq!(next(), Some(1));
+        assert_eq!(next(), Some(2));
+        handle.join().unwrap();
+        assert_eq!(next(), None);
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn sink_test() {
+        use futures_sink::Sink;
+
+        let (mut tx, mut rx) = sync_channel::<i32>(1);
+        let handle = thread::spawn(move || {
+            for i in 0..5 {
+                block_on(std::future::poll_fn(|cx| Pin::new(&mut tx).poll_ready(cx))).unwrap();
+                Pin::new(&mut tx).start_send(i).unwrap();
+            }
+            block_on(std::future::poll_fn(|cx| Pin::new(&mut tx).poll_flush(cx))).unwrap();
+            tx
+        });
+        let got: Vec<_> = (0..5).map(|_| rx.recv().unwrap()).collect();
+        assert_eq!(got, (0..5).collect::<Vec<_>>());
+        let mut tx = handle.join().unwrap();
+        drop(rx);
+        let ready = block_on(std::future::poll_fn(|cx| Pin::new(&mut tx).poll_ready(cx)));
+        assert_eq!(ready, Err(SendError::Disconnected(())));
+    }
+}
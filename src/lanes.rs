@@ -0,0 +1,136 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    store::{self, Store},
+    RecvTimeoutError, SendError, TryRecvError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    const COUNT: usize = 3;
+
+    fn lane(self) -> usize {
+        self as usize
+    }
+}
+
+pub struct Sender<T> {
+    inner: store::Sender<Lanes<T>>,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Sender {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        self.send_with_priority(t, Priority::Normal)
+    }
+
+    pub fn send_with_priority(&self, t: T, priority: Priority) -> Result<(), SendError<T>> {
+        self.inner
+            .send(t, |lanes, t| lanes[priority.lane()].push_back(t))
+    }
+}
+
+pub struct Receiver<T> {
+    inner: store::Receiver<Lanes<T>>,
+}
+
+impl<T> Receiver<T> {
+    pub fn recv(&mut self) -> Option<T> {
+        self.inner.recv()
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.inner.try_recv()
+    }
+
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.inner.recv_timeout(timeout)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Iterator for Receiver<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+type Lanes<T> = [VecDeque<T>; Priority::COUNT];
+
+impl<T> Store for Lanes<T> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        self.iter().map(VecDeque::len).sum()
+    }
+
+    fn pop(&mut self) -> Result<T, Option<Instant>> {
+        self.iter_mut().find_map(VecDeque::pop_front).ok_or(None)
+    }
+}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let (tx, rx) = store::channel(Default::default());
+    (Sender { inner: tx }, Receiver { inner: rx })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lanes_test() {
+        let (tx, rx) = channel::<&str>();
+        tx.send_with_priority("low", Priority::Low).unwrap();
+        tx.send("normal-1").unwrap();
+        tx.send_with_priority("high-1", Priority::High).unwrap();
+        tx.send("normal-2").unwrap();
+        tx.send_with_priority("high-2", Priority::High).unwrap();
+        assert_eq!(rx.len(), 5);
+        drop(tx);
+        assert_eq!(
+            rx.collect::<Vec<_>>(),
+            vec!["high-1", "high-2", "normal-1", "normal-2", "low"]
+        );
+    }
+
+    #[test]
+    fn lanes_disconnect_test() {
+        let (tx, mut rx) = channel::<i32>();
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        drop(tx);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+
+        let (tx, rx) = channel::<i32>();
+        drop(rx);
+        assert_eq!(
+            tx.send_with_priority(1, Priority::High),
+            Err(SendError::Disconnected(1))
+        );
+    }
+}
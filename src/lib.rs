@@ -1,7 +1,102 @@
-use std::{
-    collections::VecDeque,
-    sync::{Arc, Condvar, Mutex},
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+mod adapter;
+// Its const constructor can't use loom's locks.
+#[cfg(not(loom))]
+pub mod array;
+#[cfg(feature = "std")]
+pub mod broadcast;
+#[cfg(feature = "std")]
+pub mod compat;
+#[cfg(feature = "std")]
+pub mod conflate;
+#[cfg(feature = "std")]
+pub mod delay;
+#[cfg(feature = "std")]
+mod erased;
+#[cfg(all(feature = "std", unix))]
+mod fd;
+mod future;
+#[cfg(feature = "std")]
+pub mod lanes;
+pub mod local;
+#[cfg(feature = "std")]
+pub mod lockfree;
+#[cfg(feature = "std")]
+pub mod mailbox;
+#[cfg(feature = "std")]
+pub mod mux;
+#[cfg(feature = "std")]
+pub mod oneshot;
+mod padded;
+mod permit;
+#[cfg(feature = "std")]
+pub mod priority;
+#[cfg(feature = "std")]
+pub mod pubsub;
+#[cfg(feature = "std")]
+mod rate;
+#[cfg(feature = "std")]
+pub mod router;
+#[cfg(feature = "std")]
+pub mod rpc;
+#[cfg(feature = "std")]
+mod select;
+#[cfg(feature = "std")]
+pub mod spsc;
+#[cfg(feature = "std")]
+mod store;
+mod sync;
+#[cfg(feature = "std")]
+mod timer;
+mod waiters;
+#[cfg(feature = "std")]
+pub mod watch;
+
+pub use adapter::{forward, Filter, FilterMap, Map, MappedSender};
+#[cfg(feature = "std")]
+pub use erased::{AnyReceiver, AnySendError, AnySender, AnyValue, RecvAsError};
+pub use future::{ClosedFuture, RecvFuture, Selectable, SendFuture};
+pub use permit::{OwnedPermit, Permit, TryReserveError};
+#[cfg(feature = "std")]
+pub use rate::RateLimited;
+#[cfg(feature = "std")]
+pub use select::{merge, Merge, Select};
+#[cfg(not(feature = "std"))]
+pub use sync::{set_blocker, Blocker, Spin};
+#[cfg(feature = "std")]
+pub use timer::{after, tick};
+
+use alloc::{
+    boxed::Box,
+    collections::{vec_deque, VecDeque},
+    vec::Vec,
 };
+use core::{
+    error::Error,
+    fmt,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+#[cfg(feature = "std")]
+use std::thread::{Thread, ThreadId};
+
+use padded::CachePadded;
+#[cfg(feature = "std")]
+use select::SelectHandle;
+use sync::{Arc, Condvar, Instant, Mutex, MutexGuard};
+use waiters::WaitList;
+
+// Emits a `tracing` event tagged with the channel's name; compiles to nothing
+// without the `tracing` feature.
+macro_rules! event {
+    ($level:ident, $shared:expr, $($rest:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!(channel = $shared.name.as_deref().unwrap_or("unnamed"), $($rest)+);
+    };
+}
 
 pub struct Sender<T> {
     shared: Arc<Shared<T>>,
@@ -9,9 +104,7 @@ pub struct Sender<T> {
 
 impl<T> Clone for Sender<T> {
     fn clone(&self) -> Self {
-        let mut inner = self.shared.inner.lock().unwrap();
-        inner.senders += 1;
-        drop(inner);
+        self.shared.senders.fetch_add(1, Ordering::Relaxed);
         Sender {
             shared: Arc::clone(&self.shared),
         }
@@ -19,20 +112,352 @@ impl<T> Clone for Sender<T> {
 }
 
 impl<T> Sender<T> {
-    pub fn send(&mut self, t: T) {
-        let mut inner = self.shared.inner.lock().unwrap();
-        inner.queue.push_back(t);
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        self.shared.send_until(t, None).map_err(|e| match e {
+            SendTimeoutError::Closed(t) => SendError::Closed(t),
+            // Without a deadline only a rejecting channel gives up early.
+            SendTimeoutError::Timeout(t) => SendError::Full(t),
+            SendTimeoutError::Disconnected(t) => SendError::Disconnected(t),
+        })
+    }
+
+    pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        let mut inner = self.shared.inner.lock();
+        if let Some(refusal) = inner.refusal() {
+            return Err(refusal.try_send(t));
+        }
+        if inner.is_full_nonblocking(&t) || !inner.is_next(None) {
+            match inner.overflow(&t) {
+                Some(true) => {}
+                Some(false) => return Ok(()),
+                None => return Err(TrySendError::Full(t)),
+            }
+        }
+        self.shared.push(&mut inner, t);
+        if inner.defers_wake() {
+            return Ok(());
+        }
+        inner.wake_receivers();
+        drop(inner);
+        self.shared.available.notify_one();
+        Ok(())
+    }
+
+    // Like `try_send`, but the message goes to the head of the queue, ahead
+    // of everything already queued and of any blocked senders.
+    pub fn send_front(&self, t: T) -> Result<(), TrySendError<T>> {
+        let mut inner = self.shared.inner.lock();
+        if let Some(refusal) = inner.refusal() {
+            return Err(refusal.try_send(t));
+        }
+        if inner.is_full_nonblocking(&t) {
+            match inner.overflow(&t) {
+                Some(true) => {}
+                Some(false) => return Ok(()),
+                None => return Err(TrySendError::Full(t)),
+            }
+        }
+        self.shared.push_at(&mut inner, t, true);
+        if inner.defers_wake() {
+            return Ok(());
+        }
+        inner.wake_receivers();
+        drop(inner);
+        self.shared.available.notify_one();
+        Ok(())
+    }
+
+    pub fn force_send(&self, t: T) -> Result<Option<T>, SendError<T>> {
+        let mut inner = self.shared.inner.lock();
+        if let Some(refusal) = inner.refusal() {
+            return Err(refusal.send(t));
+        }
+        let mut evicted = None;
+        if inner.is_full_nonblocking(&t) {
+            // Evicting a message mid-handoff would strand its sender, nothing
+            // is dropped while paused, and slots held by permits can't be
+            // freed by evicting.
+            let reserved = inner.capacity.is_some_and(|cap| inner.reserved >= cap);
+            if inner.capacity == Some(0) || inner.paused || reserved {
+                return Err(SendError::Full(t));
+            }
+            // Under a byte budget one eviction may not be enough. Only the
+            // oldest message is handed back; the others count as dropped.
+            evicted = inner.pop();
+            while inner.lacks_room(&t) && inner.pop().is_some() {
+                inner.dropped += 1;
+            }
+        }
+        self.shared.push(&mut inner, t);
+        inner.wake_receivers();
         drop(inner);
         self.shared.available.notify_one();
+        Ok(evicted)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn send_timeout(&self, t: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        self.shared
+            .send_until(t, Instant::now().checked_add(timeout))
+    }
+
+    pub fn send_all<I: IntoIterator<Item = T>>(&self, iter: I) -> Result<(), SendError<Vec<T>>> {
+        // Collect before locking so the caller's iterator never runs while we
+        // hold the channel lock.
+        let mut items = VecDeque::from(iter.into_iter().collect::<Vec<_>>());
+        let mut inner = self.shared.inner.lock();
+        let mut ticket = None;
+        loop {
+            if let Some(refusal) = inner.refusal() {
+                self.shared.leave_queue(&mut inner, ticket);
+                return Err(refusal.send(items.into()));
+            }
+            if items.is_empty() {
+                return Ok(());
+            }
+            if inner.capacity == Some(0) {
+                drop(inner);
+                while let Some(t) = items.pop_front() {
+                    if let Err(e) = self.send(t) {
+                        let closed = matches!(e, SendError::Closed(_));
+                        items.push_front(e.into_inner());
+                        return Err(if closed {
+                            SendError::Closed(items.into())
+                        } else {
+                            SendError::Disconnected(items.into())
+                        });
+                    }
+                }
+                return Ok(());
+            }
+            // Senders already waiting go first; once it's our turn we keep
+            // it until the whole batch is in.
+            if inner.is_next(ticket) {
+                // Hand over our allocation unless the queue's own is big
+                // enough. Only when nothing is recorded per message, as the
+                // swap skips `push`.
+                if !inner.is_bounded()
+                    && !inner.tracks_pushes()
+                    && self.shared.metrics.is_none()
+                    && inner.queue.is_empty()
+                    && inner.queue.capacity() < items.len()
+                {
+                    core::mem::swap(&mut inner.queue, &mut items);
+                }
+                while let Some(t) = items.pop_front() {
+                    if inner.lacks_room(&t) {
+                        match inner.overflow(&t) {
+                            Some(true) => {}
+                            Some(false) => continue,
+                            None => {
+                                items.push_front(t);
+                                break;
+                            }
+                        }
+                    }
+                    self.shared.push(&mut inner, t);
+                }
+                inner.wake_receivers();
+                let many = inner.receivers > 1;
+                if !items.is_empty() && inner.policy == OverflowPolicy::Reject {
+                    self.shared.leave_queue(&mut inner, ticket);
+                    drop(inner);
+                    self.shared.available.notify_all();
+                    return Err(SendError::Full(items.into()));
+                }
+                if items.is_empty() {
+                    self.shared.leave_queue(&mut inner, ticket);
+                    drop(inner);
+                    if many {
+                        self.shared.available.notify_all();
+                    } else {
+                        self.shared.available.notify_one();
+                    }
+                    return Ok(());
+                }
+                self.shared.available.notify_all();
+            }
+            ticket = ticket.or_else(|| Some(inner.join_queue()));
+            inner = self.shared.space.wait(inner);
+        }
+    }
+
+    // All or nothing: if the whole batch doesn't fit right now it's handed
+    // back untouched, whatever the overflow policy. Receivers are woken once.
+    pub fn send_batch<I: IntoIterator<Item = T>>(
+        &self,
+        iter: I,
+    ) -> Result<(), TrySendError<Vec<T>>> {
+        let items: Vec<T> = iter.into_iter().collect();
+        let mut inner = self.shared.inner.lock();
+        if let Some(refusal) = inner.refusal() {
+            return Err(refusal.try_send(items));
+        }
+        if items.is_empty() {
+            return Ok(());
+        }
+        if !inner.fits(&items) || !inner.is_next(None) {
+            return Err(TrySendError::Full(items));
+        }
+        for t in items {
+            self.shared.push(&mut inner, t);
+        }
+        inner.wake_receivers();
+        let many = inner.receivers > 1;
+        drop(inner);
+        if many {
+            self.shared.available.notify_all();
+        } else {
+            self.shared.available.notify_one();
+        }
+        Ok(())
+    }
+
+    // Closes the channel from the sending side and takes what's still
+    // queued; messages already in a receiver's buffer stay there.
+    pub fn close_and_drain(&self) -> vec_deque::IntoIter<T> {
+        self.shared.close();
+        self.shared.resume();
+        self.shared.drain(VecDeque::new()).into_iter()
+    }
+
+    pub fn wait_closed(&self) {
+        let mut inner = self.shared.inner.lock();
+        while inner.refusal().is_none() {
+            inner = self.shared.closing.wait(inner);
+        }
+    }
+
+    pub fn closed(&self) -> ClosedFuture<'_, T> {
+        ClosedFuture { sender: self }
+    }
+
+    pub fn same_channel(&self, other: &Sender<T>) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+
+    pub fn downgrade(&self) -> WeakSender<T> {
+        let mut inner = self.shared.inner.lock();
+        inner.weak_senders += 1;
+        drop(inner);
+        WeakSender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    pub fn send_async(&self, t: T) -> SendFuture<'_, T> {
+        SendFuture {
+            sender: self,
+            value: Some(t),
+            handoff: None,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.shared.inner.lock().refusal().is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.shared.inner.lock().capacity
+    }
+
+    pub fn remaining(&self) -> Option<usize> {
+        self.shared.inner.lock().remaining()
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.shared.inner.lock().dropped
+    }
+
+    // Weak senders don't count.
+    pub fn sender_count(&self) -> usize {
+        self.shared.senders.load(Ordering::Relaxed)
+    }
+
+    pub fn receiver_count(&self) -> usize {
+        self.shared.inner.lock().receivers
+    }
+
+    pub fn stats(&self) -> Option<ChannelStats> {
+        self.shared.stats()
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.shared.debug(f, "Sender")
     }
 }
 
 impl<T> Drop for Sender<T> {
     fn drop(&mut self) {
-        let mut inner = self.shared.inner.lock().unwrap();
-        inner.senders -= 1;
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) != 1 {
+            return;
+        }
+        // Flag the disconnect under the lock so a receiver can't check it
+        // just before we notify and then sleep through the wakeup.
+        let mut inner = self.shared.inner.lock();
+        // A receiver may have reopened the channel in the meantime.
+        if self.shared.senders.load(Ordering::Acquire) != 0 {
+            return;
+        }
+        inner.disconnected = true;
+        event!(debug, self.shared, "all senders dropped");
+        inner.wake_receivers();
         drop(inner);
-        self.shared.available.notify_one();
+        self.shared.available.notify_all();
+    }
+}
+
+pub struct WeakSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for WeakSender<T> {
+    fn clone(&self) -> Self {
+        let mut inner = self.shared.inner.lock();
+        inner.weak_senders += 1;
+        drop(inner);
+        WeakSender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> WeakSender<T> {
+    pub fn upgrade(&self) -> Option<Sender<T>> {
+        // Once the last strong sender is gone the receiver may already have
+        // observed the disconnect, so the channel must stay closed.
+        self.shared
+            .senders
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |n| {
+                (n > 0).then_some(n + 1)
+            })
+            .ok()?;
+        Some(Sender {
+            shared: Arc::clone(&self.shared),
+        })
+    }
+}
+
+impl<T> fmt::Debug for WeakSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.shared.debug(f, "WeakSender")
+    }
+}
+
+impl<T> Drop for WeakSender<T> {
+    fn drop(&mut self) {
+        let mut inner = self.shared.inner.lock();
+        inner.weak_senders -= 1;
     }
 }
 
@@ -41,101 +466,3062 @@ pub struct Receiver<T> {
     buffer: VecDeque<T>,
 }
 
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        let mut inner = self.shared.inner.lock();
+        inner.receivers += 1;
+        drop(inner);
+        Receiver {
+            shared: Arc::clone(&self.shared),
+            buffer: VecDeque::new(),
+        }
+    }
+}
+
 impl<T> Receiver<T> {
     pub fn recv(&mut self) -> Option<T> {
-        if let Some(t) = self.buffer.pop_front() {
-            return Some(t);
+        self.recv_until(None).ok()
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        if let Some(t) = self.pop_buffered() {
+            return Ok(t);
         }
-        let mut inner = self.shared.inner.lock().unwrap();
-        loop {
-            match inner.queue.pop_front() {
-                Some(t) => {
-                    std::mem::swap(&mut self.buffer, &mut inner.queue);
-                    return Some(t);
-                }
-                None if inner.senders == 0 => return None,
-                None => inner = self.shared.available.wait(inner).unwrap(),
-            }
+        let mut inner = self.shared.inner.lock();
+        match self.shared.take(&mut inner, &mut self.buffer) {
+            Some(t) => Ok(t),
+            None if inner.ends_receiving() => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
         }
     }
-}
 
-impl<T> Iterator for Receiver<T> {
-    type Item = T;
-    fn next(&mut self) -> Option<Self::Item> {
-        self.recv()
+    #[cfg(feature = "std")]
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Instant::now().checked_add(timeout))
     }
-}
 
-struct Shared<T> {
-    inner: Mutex<Inner<T>>,
-    available: Condvar,
-}
+    #[cfg(feature = "std")]
+    pub fn recv_deadline(&mut self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Some(deadline))
+    }
 
-struct Inner<T> {
-    queue: VecDeque<T>,
-    senders: usize,
-}
+    // The latency is zero unless the channel was built with `track_latency`.
+    pub fn recv_with_latency(&mut self) -> Option<(T, Duration)> {
+        let (t, stamp) = self.recv_timed_until(None).ok()?;
+        Some((t, stamp.latency.unwrap_or_default()))
+    }
 
-pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
-    let inner = Inner {
-        queue: VecDeque::default(),
-        senders: 1,
-    };
+    // Numbers start at 1 and go up by one per message sent, including those
+    // an overflow policy or TTL threw away, so gaps reveal drops. They're 0
+    // unless the channel was built with `sequence_numbers`, and for messages
+    // that had already left the queue, like peeked ones.
+    pub fn recv_with_seq(&mut self) -> Option<(u64, T)> {
+        let (t, stamp) = self.recv_timed_until(None).ok()?;
+        Some((stamp.seq, t))
+    }
 
-    let shared = Shared {
-        inner: Mutex::new(inner),
-        available: Condvar::new(),
-    };
+    pub fn latency_stats(&self) -> Option<LatencyStats> {
+        self.shared.inner.lock().latency
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.shared.inner.lock().capacity
+    }
+
+    pub fn remaining(&self) -> Option<usize> {
+        self.shared.inner.lock().remaining()
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.shared.inner.lock().dropped
+    }
+
+    // Weak senders don't count.
+    pub fn sender_count(&self) -> usize {
+        self.shared.senders.load(Ordering::Relaxed)
+    }
+
+    pub fn receiver_count(&self) -> usize {
+        self.shared.inner.lock().receivers
+    }
+
+    pub fn stats(&self) -> Option<ChannelStats> {
+        self.shared.stats()
+    }
 
-    let shared = Arc::new(shared);
+    pub fn close(&mut self) {
+        self.shared.close();
+    }
 
-    (
+    // Reopens the channel if every sender is gone: receivers that already
+    // saw the disconnect will block for messages again.
+    pub fn sender(&self) -> Sender<T> {
+        let mut inner = self.shared.inner.lock();
+        self.shared.senders.fetch_add(1, Ordering::Relaxed);
+        inner.disconnected = false;
+        drop(inner);
         Sender {
-            shared: shared.clone(),
-        },
-        Receiver {
-            shared: shared.clone(),
-            buffer: VecDeque::new(),
-        },
-    )
-}
+            shared: Arc::clone(&self.shared),
+        }
+    }
 
-#[cfg(test)]
-mod test {
-    use std::thread;
+    // Closes the channel and hands back everything still waiting in it,
+    // this receiver's buffer first. Blocked senders fail with `Closed`.
+    pub fn close_and_drain(&mut self) -> vec_deque::IntoIter<T> {
+        self.shared.close();
+        self.resume();
+        self.drain().into_iter()
+    }
 
-    use super::*;
+    // Holds off every receiver until `resume`. Messages keep queueing, and
+    // once a bounded channel fills up its senders wait, whatever the overflow
+    // policy, so nothing is dropped in the meantime.
+    pub fn pause(&mut self) {
+        let mut inner = self.shared.inner.lock();
+        inner.paused = true;
+        self.shared.return_buffer(&mut inner, &mut self.buffer);
+    }
 
-    #[test]
-    fn single_sender_test() {
-        let (mut tx, mut rx) = channel::<i32>();
-        tx.send(43);
-        assert_eq!(rx.recv(), Some(43));
+    pub fn resume(&self) {
+        self.shared.resume();
     }
 
-    #[test]
-    fn single_sender_drop_test() {
-        let (tx, mut rx) = channel::<i32>();
-        drop(tx);
-        assert_eq!(rx.recv(), None);
+    pub fn is_paused(&self) -> bool {
+        self.shared.inner.lock().paused
     }
 
-    #[test]
-    fn multi_send_test() {
-        let (mut tx, mut rx) = channel::<i32>();
-        let mut tx2 = tx.clone();
-        thread::spawn(move || {
-            tx.send(1);
+    pub fn recv_many(&mut self, buf: &mut Vec<T>, limit: usize) -> usize {
+        if limit == 0 {
+            return 0;
+        }
+        match self.recv() {
+            Some(t) => buf.push(t),
+            None => return 0,
+        }
+        1 + self.fill(buf, limit - 1)
+    }
+
+    // Like `recv_many`, but makes room for everything ready (up to `max`) in
+    // one go, so a reused buffer that's already big enough never reallocates.
+    pub fn recv_into(&mut self, buf: &mut Vec<T>, max: usize) -> usize {
+        if max == 0 {
+            return 0;
+        }
+        match self.recv() {
+            Some(t) => self.append_ready(buf, t, max),
+            None => 0,
+        }
+    }
+
+    pub fn try_recv_into(&mut self, buf: &mut Vec<T>, max: usize) -> Result<usize, TryRecvError> {
+        if max == 0 {
+            return Ok(0);
+        }
+        let t = self.try_recv()?;
+        Ok(self.append_ready(buf, t, max))
+    }
+
+    pub fn drain(&mut self) -> VecDeque<T> {
+        let drained = core::mem::take(&mut self.buffer);
+        self.shared
+            .buffered
+            .fetch_sub(drained.len(), Ordering::Relaxed);
+        self.shared.drain(drained)
+    }
+
+    // Everything this receiver could still have received, for persisting
+    // unprocessed work on shutdown. The last receiver closes the channel
+    // first, so nothing sent in the meantime is lost; any other leaves the
+    // queue to its clones and only hands back its own buffer.
+    pub fn into_remaining(mut self) -> Vec<T> {
+        // Counted under the same lock as the drain, so a clone dropping in
+        // between can't leave the queue to nobody.
+        let mut inner = self.shared.inner.lock();
+        if inner.receivers > 1 {
+            drop(inner);
+            self.shared
+                .buffered
+                .fetch_sub(self.buffer.len(), Ordering::Relaxed);
+            self.shared.record_received(self.buffer.len());
+            return self.buffer.drain(..).collect();
+        }
+        let closed = self.shared.mark_closed(&mut inner);
+        inner.paused = false;
+        let buffer = core::mem::take(&mut self.buffer);
+        self.shared
+            .buffered
+            .fetch_sub(buffer.len(), Ordering::Relaxed);
+        let drained = self.shared.drain_locked(&mut inner, buffer);
+        drop(inner);
+        if closed {
+            self.shared.notify_closed();
+        }
+        self.shared.record_received(drained.len());
+        drained.into()
+    }
+
+    // Hands back memory left over from a burst: the queue and this
+    // receiver's buffer keep their peak capacity otherwise. Neither shrinks
+    // below what the builder preallocated.
+    pub fn shrink_to_fit(&mut self) {
+        let mut inner = self.shared.inner.lock();
+        let floor = inner.preallocate;
+        inner.queue.shrink_to(floor);
+        inner.sent_at.shrink_to_fit();
+        inner.seqs.shrink_to_fit();
+        inner.send_queue.shrink_to_fit();
+        drop(inner);
+        self.buffer.shrink_to(floor);
+    }
+
+    pub fn into_drain(self) -> DrainReceiver<T> {
+        DrainReceiver { receiver: self }
+    }
+
+    pub fn iter(&mut self) -> Iter<'_, T> {
+        Iter { receiver: self }
+    }
+
+    pub fn try_iter(&mut self) -> TryIter<'_, T> {
+        TryIter { receiver: self }
+    }
+
+    // A background thread copies every message to both returned receivers
+    // until the input disconnects or both outputs are dropped. The outputs
+    // share the input's capacity, so a slow consumer holds back the other.
+    #[cfg(feature = "std")]
+    pub fn tee(self) -> (Receiver<T>, Receiver<T>)
+    where
+        T: Clone + Send + 'static,
+    {
+        let [(tx1, rx1), (tx2, rx2)] = [(); 2].map(|_| match self.capacity() {
+            Some(capacity) => sync_channel(capacity),
+            None => channel(),
         });
+        std::thread::Builder::new()
+            .name("channel-tee".into())
+            .spawn(move || {
+                let mut input = self;
+                let mut outputs = vec![tx1, tx2];
+                while !outputs.is_empty() {
+                    let Some(t) = input.recv() else {
+                        return;
+                    };
+                    outputs.retain(|tx| tx.send(t.clone()).is_ok());
+                }
+            })
+            .expect("failed to spawn tee thread");
+        (rx1, rx2)
+    }
 
-        thread::spawn(move || tx2.send(2));
+    // Workers compete for messages; returns once the channel disconnects and
+    // every worker is done. A panic in `f` is re-raised here.
+    #[cfg(feature = "std")]
+    pub fn for_each_parallel<F>(self, threads: usize, f: F)
+    where
+        T: Send,
+        F: Fn(T) + Sync,
+    {
+        assert!(threads > 0, "for_each_parallel needs at least one thread");
+        let f = &f;
+        std::thread::scope(|scope| {
+            let mut receivers = vec![self];
+            for _ in 1..threads {
+                receivers.push(receivers[0].clone());
+            }
+            for rx in receivers {
+                std::thread::Builder::new()
+                    .name("channel-worker".into())
+                    .spawn_scoped(scope, move || rx.for_each(f))
+                    .expect("failed to spawn worker thread");
+            }
+        });
+    }
 
-        let f1 = rx.recv();
-        let f2 = rx.recv();
-        let end = rx.recv();
-        assert_eq!(f1, Some(1));
-        assert_eq!(f2, Some(2));
-        assert_eq!(end, None);
+    pub fn recv_async(&mut self) -> RecvFuture<'_, T> {
+        RecvFuture { receiver: self }
+    }
+
+    pub fn peek(&mut self) -> Option<&T> {
+        if self.buffer.is_empty() {
+            let t = self.recv()?;
+            self.unpop(t);
+        }
+        self.buffer.front()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn peek_timeout(&mut self, timeout: Duration) -> Result<&T, RecvTimeoutError> {
+        if self.buffer.is_empty() {
+            let t = self.recv_timeout(timeout)?;
+            self.unpop(t);
+        }
+        Ok(self.buffer.front().expect("just buffered"))
+    }
+
+    // Hands a message back to be received again before anything else, say
+    // to retry it. It stays with this receiver and takes no slot in the
+    // channel, so this never blocks.
+    pub fn requeue(&mut self, t: T) {
+        self.unpop(t);
+    }
+
+    // Messages that don't match are set aside in the private buffer, in
+    // order, for later receives; they no longer count against the capacity,
+    // so other traffic keeps flowing while we wait.
+    pub fn recv_filter<P: FnMut(&T) -> bool>(&mut self, mut pred: P) -> Option<T> {
+        let mut checked = 0;
+        loop {
+            if let Some(i) = self.buffer.iter().skip(checked).position(&mut pred) {
+                let t = self.buffer.remove(checked + i).expect("just found");
+                self.shared.buffered.fetch_sub(1, Ordering::Relaxed);
+                self.shared.record_received(1);
+                return Some(t);
+            }
+            checked = self.buffer.len();
+            // Receive past what we hold; a swap may bring more of the queue
+            // along, which goes after it.
+            let held = core::mem::take(&mut self.buffer);
+            let next = self.recv();
+            let swapped = core::mem::replace(&mut self.buffer, held);
+            let Some(t) = next else {
+                self.buffer.extend(swapped);
+                return None;
+            };
+            if pred(&t) {
+                self.buffer.extend(swapped);
+                return Some(t);
+            }
+            self.buffer.push_back(t);
+            self.unreceive();
+            self.buffer.extend(swapped);
+            checked += 1;
+        }
+    }
+
+    // Peeked and selected messages are parked at the front of the private
+    // buffer, which keeps them ahead of anything swapped in after them.
+    fn unpop(&mut self, t: T) {
+        self.buffer.push_front(t);
+        self.unreceive();
+    }
+
+    // Accounts for a received message that went back into the buffer.
+    fn unreceive(&self) {
+        self.shared.buffered.fetch_add(1, Ordering::Relaxed);
+        if let Some(metrics) = &self.shared.metrics {
+            metrics.received.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    // Moves up to `limit` messages into `buf` without blocking, touching the
+    // lock at most once.
+    fn append_ready(&mut self, buf: &mut Vec<T>, first: T, max: usize) -> usize {
+        buf.reserve(1 + self.len().min(max - 1));
+        buf.push(first);
+        1 + self.fill(buf, max - 1)
+    }
+
+    fn fill(&mut self, buf: &mut Vec<T>, limit: usize) -> usize {
+        let from_buffer = limit.min(self.buffer.len());
+        buf.extend(self.buffer.drain(..from_buffer));
+        self.shared
+            .buffered
+            .fetch_sub(from_buffer, Ordering::Relaxed);
+        self.shared.record_received(from_buffer);
+        if from_buffer == limit {
+            return limit;
+        }
+        let mut inner = self.shared.inner.lock();
+        self.shared.expire(&mut inner);
+        let before = buf.len();
+        let wanted = limit - from_buffer;
+        buf.extend((0..wanted).map_while(|_| inner.pop_received().map(|(t, _)| t)));
+        let from_queue = buf.len() - before;
+        inner.taken += from_queue as u64;
+        inner.lower_signal();
+        if from_queue > 0 && inner.is_bounded() {
+            inner.wake_senders();
+            drop(inner);
+            self.shared.space.notify_all();
+        }
+        self.shared.record_received(from_queue);
+        from_buffer + from_queue
+    }
+
+    fn pop_buffered(&mut self) -> Option<T> {
+        let t = self.buffer.pop_front()?;
+        self.shared.buffered.fetch_sub(1, Ordering::Relaxed);
+        self.shared.record_received(1);
+        Some(t)
+    }
+
+    fn recv_until(&mut self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        self.recv_timed_until(deadline).map(|(t, _)| t)
+    }
+
+    fn recv_timed_until(
+        &mut self,
+        deadline: Option<Instant>,
+    ) -> Result<(T, Stamp), RecvTimeoutError> {
+        // Peeked and selected messages have already left the queue, along
+        // with their timestamps and sequence numbers.
+        if let Some(t) = self.pop_buffered() {
+            return Ok((t, Stamp::default()));
+        }
+        let mut inner = self.shared.inner.lock();
+        let mut spun = false;
+        loop {
+            // A caller whose own deadline comes first gets whatever is there
+            // by then.
+            #[cfg(feature = "std")]
+            if let Some(due) = inner
+                .batch_deadline()
+                .filter(|_| deadline.is_none_or(|d| Instant::now() < d))
+            {
+                let until = deadline.map_or(due, |d| d.min(due));
+                inner = wait_until(&self.shared.available, inner, Some(until)).0;
+                continue;
+            }
+            match self.shared.take_timed(&mut inner, &mut self.buffer) {
+                Some(entry) => return Ok(entry),
+                None if inner.ends_receiving() => return Err(RecvTimeoutError::Disconnected),
+                None if !spun && self.shared.spin > 0 => {
+                    spun = true;
+                    drop(inner);
+                    self.shared.spin();
+                    inner = self.shared.inner.lock();
+                }
+                None => {
+                    inner.waiting += 1;
+                    event!(debug, self.shared, "recv blocking");
+                    let (guard, timed_out) = wait_until(&self.shared.available, inner, deadline);
+                    inner = guard;
+                    inner.waiting -= 1;
+                    if timed_out {
+                        return Err(RecvTimeoutError::Timeout);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.shared.debug(f, "Receiver")
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut inner = self.shared.inner.lock();
+        inner.receivers -= 1;
+        let last = inner.receivers == 0;
+        if !last {
+            // What we held is still owed to the remaining receivers.
+            let held = !self.buffer.is_empty();
+            self.shared.return_buffer(&mut inner, &mut self.buffer);
+            if held {
+                inner.wake_receivers();
+            }
+            drop(inner);
+            if held {
+                self.shared.available.notify_all();
+            }
+            self.shared.space.notify_all();
+            return;
+        }
+        self.shared
+            .buffered
+            .fetch_sub(self.buffer.len(), Ordering::Relaxed);
+        // Nobody is left to receive what we held or what's still queued.
+        if let Some(mut on_drop) = inner.on_drop.take() {
+            self.buffer.drain(..).for_each(&mut on_drop);
+            while let Some(t) = inner.pop() {
+                on_drop(t);
+            }
+            inner.on_drop = Some(on_drop);
+        }
+        event!(debug, self.shared, "all receivers dropped");
+        inner.wake_senders();
+        drop(inner);
+        self.shared.space.notify_all();
+        self.shared.closing.notify_all();
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> SelectHandle for Receiver<T> {
+    fn poll_ready(&mut self) -> bool {
+        if !self.buffer.is_empty() {
+            return true;
+        }
+        // Claim the message now so a competing receiver can't take it between
+        // the select returning and the caller's `try_recv`.
+        let mut inner = self.shared.inner.lock();
+        let t = self.shared.take(&mut inner, &mut self.buffer);
+        let disconnected = inner.ends_receiving();
+        drop(inner);
+        match t {
+            Some(t) => {
+                self.unpop(t);
+                true
+            }
+            None => disconnected,
+        }
+    }
+
+    fn watch(&self, thread: &Thread) {
+        let mut inner = self.shared.inner.lock();
+        inner.recv_waiters.watch(thread);
+    }
+
+    fn unwatch(&self, id: ThreadId) {
+        let mut inner = self.shared.inner.lock();
+        inner.recv_waiters.unwatch(id);
+    }
+}
+
+impl<T> Iterator for Receiver<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Disconnected,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => "receiving on an empty channel".fmt(f),
+            TryRecvError::Disconnected => "receiving on a closed channel".fmt(f),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvTimeoutError {
+    Timeout,
+    Disconnected,
+}
+
+impl fmt::Display for RecvTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvTimeoutError::Timeout => "timed out waiting on channel".fmt(f),
+            RecvTimeoutError::Disconnected => "channel is empty and sending half is closed".fmt(f),
+        }
+    }
+}
+
+impl Error for RecvTimeoutError {}
+
+pub struct Iter<'a, T> {
+    receiver: &'a mut Receiver<T>,
+}
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv()
+    }
+}
+
+pub struct TryIter<'a, T> {
+    receiver: &'a mut Receiver<T>,
+}
+
+impl<T> Iterator for TryIter<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+// A receiver for cleanup code: it takes whatever is queued and never waits
+// for more, live senders or not.
+pub struct DrainReceiver<T> {
+    receiver: Receiver<T>,
+}
+
+impl<T> DrainReceiver<T> {
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+}
+
+impl<T> Iterator for DrainReceiver<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl<T> fmt::Debug for DrainReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.receiver.shared.debug(f, "DrainReceiver")
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SendError<T> {
+    Full(T),
+    Disconnected(T),
+    Closed(T),
+}
+
+impl<T> SendError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            SendError::Full(t) | SendError::Disconnected(t) | SendError::Closed(t) => t,
+        }
+    }
+}
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::Full(_) => "Full(..)".fmt(f),
+            SendError::Disconnected(_) => "Disconnected(..)".fmt(f),
+            SendError::Closed(_) => "Closed(..)".fmt(f),
+        }
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::Full(_) => "sending on a full channel".fmt(f),
+            SendError::Disconnected(_) => "sending on a closed channel".fmt(f),
+            SendError::Closed(_) => CLOSED_TO_SENDS.fmt(f),
+        }
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    Full(T),
+    Disconnected(T),
+    Closed(T),
+}
+
+impl<T> TrySendError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            TrySendError::Full(t) | TrySendError::Disconnected(t) | TrySendError::Closed(t) => t,
+        }
+    }
+}
+
+impl<T> fmt::Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => "Full(..)".fmt(f),
+            TrySendError::Disconnected(_) => "Disconnected(..)".fmt(f),
+            TrySendError::Closed(_) => "Closed(..)".fmt(f),
+        }
+    }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => "sending on a full channel".fmt(f),
+            TrySendError::Disconnected(_) => "sending on a closed channel".fmt(f),
+            TrySendError::Closed(_) => CLOSED_TO_SENDS.fmt(f),
+        }
+    }
+}
+
+impl<T> Error for TrySendError<T> {}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SendTimeoutError<T> {
+    Timeout(T),
+    Disconnected(T),
+    Closed(T),
+}
+
+impl<T> SendTimeoutError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            SendTimeoutError::Timeout(t)
+            | SendTimeoutError::Disconnected(t)
+            | SendTimeoutError::Closed(t) => t,
+        }
+    }
+}
+
+impl<T> fmt::Debug for SendTimeoutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendTimeoutError::Timeout(_) => "Timeout(..)".fmt(f),
+            SendTimeoutError::Disconnected(_) => "Disconnected(..)".fmt(f),
+            SendTimeoutError::Closed(_) => "Closed(..)".fmt(f),
+        }
+    }
+}
+
+impl<T> fmt::Display for SendTimeoutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendTimeoutError::Timeout(_) => "timed out waiting on send operation".fmt(f),
+            SendTimeoutError::Disconnected(_) => "sending on a closed channel".fmt(f),
+            SendTimeoutError::Closed(_) => CLOSED_TO_SENDS.fmt(f),
+        }
+    }
+}
+
+impl<T> Error for SendTimeoutError<T> {}
+
+// What a bounded channel does with a message sent while it is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    #[default]
+    Block,
+    DropNewest,
+    DropOldest,
+    Reject,
+}
+
+const CLOSED_TO_SENDS: &str = "sending on a channel closed to new messages";
+
+// Why a channel refuses new messages.
+#[derive(Clone, Copy)]
+enum Refusal {
+    Disconnected,
+    Closed,
+}
+
+impl Refusal {
+    fn send<T>(self, t: T) -> SendError<T> {
+        match self {
+            Refusal::Disconnected => SendError::Disconnected(t),
+            Refusal::Closed => SendError::Closed(t),
+        }
+    }
+
+    fn try_send<T>(self, t: T) -> TrySendError<T> {
+        match self {
+            Refusal::Disconnected => TrySendError::Disconnected(t),
+            Refusal::Closed => TrySendError::Closed(t),
+        }
+    }
+
+    fn send_timeout<T>(self, t: T) -> SendTimeoutError<T> {
+        match self {
+            Refusal::Disconnected => SendTimeoutError::Disconnected(t),
+            Refusal::Closed => SendTimeoutError::Closed(t),
+        }
+    }
+}
+
+// Fields written mostly by senders (`available`, `senders`) and mostly by
+// receivers (`space`, `buffered`) sit on separate cache lines, away from the
+// lock itself.
+struct Shared<T> {
+    inner: CachePadded<Mutex<Inner<T>>>,
+    available: CachePadded<Condvar>,
+    space: CachePadded<Condvar>,
+    // Each condvar has waiters with a single predicate, so `notify_one` can't
+    // be swallowed by a thread waiting for something else.
+    closing: Condvar,
+    // Messages sitting in receivers' private buffers, which only their owner
+    // can see; counted here so `len` on either handle stays accurate.
+    buffered: CachePadded<AtomicUsize>,
+    // Kept out of `Inner` so cloning and dropping senders doesn't contend
+    // with the data path.
+    senders: CachePadded<AtomicUsize>,
+    metrics: Option<Metrics>,
+    spin: u32,
+    name: Option<Box<str>>,
+}
+
+// Atomic so the lock-free private buffer path can count deliveries too.
+#[derive(Default)]
+struct Metrics {
+    sent: CachePadded<AtomicU64>,
+    received: CachePadded<AtomicU64>,
+    peak_depth: AtomicUsize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChannelStats {
+    pub sent: u64,
+    pub received: u64,
+    pub dropped: u64,
+    pub expired: u64,
+    pub depth: usize,
+    pub peak_depth: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LatencyStats {
+    pub count: u64,
+    pub last: Duration,
+    pub max: Duration,
+    // Exponentially weighted, giving the latest sample a weight of 1/8.
+    pub smoothed: Duration,
+}
+
+impl LatencyStats {
+    fn record(&mut self, latency: Duration) {
+        self.smoothed = if self.count == 0 {
+            latency
+        } else {
+            (self.smoothed * 7 + latency) / 8
+        };
+        self.count += 1;
+        self.last = latency;
+        self.max = self.max.max(latency);
+    }
+}
+
+impl<T> Shared<T> {
+    fn len(&self) -> usize {
+        let inner = self.inner.lock();
+        inner.queue.len() + self.buffered.load(Ordering::Relaxed)
+    }
+
+    // Formatting may happen while this thread already holds the lock, e.g.
+    // from a dead letter callback, so only the identity is printed if the
+    // lock is busy.
+    fn debug(&self, f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+        let mut d = f.debug_struct(name);
+        d.field("channel", &(self as *const Self));
+        let Some(inner) = self.inner.try_lock() else {
+            return d.finish_non_exhaustive();
+        };
+        d.field(
+            "len",
+            &(inner.queue.len() + self.buffered.load(Ordering::Relaxed)),
+        )
+        .field("capacity", &inner.capacity)
+        .field("senders", &self.senders.load(Ordering::Relaxed))
+        .field("receivers", &inner.receivers)
+        .field("closed", &inner.closed);
+        if let Some(name) = &self.name {
+            d.field("name", name);
+        }
+        d.finish()
+    }
+
+    // Puts a receiver's private buffer back at the head of the queue, ahead
+    // of everything sent since, for whoever receives next.
+    fn return_buffer(&self, inner: &mut Inner<T>, buffer: &mut VecDeque<T>) {
+        self.buffered.fetch_sub(buffer.len(), Ordering::Relaxed);
+        for t in buffer.drain(..).rev() {
+            inner.push_next(t);
+        }
+    }
+
+    fn resume(&self) {
+        let mut inner = self.inner.lock();
+        if !inner.paused {
+            return;
+        }
+        inner.paused = false;
+        inner.wake_receivers();
+        drop(inner);
+        self.available.notify_all();
+    }
+
+    fn close(&self) {
+        let mut inner = self.inner.lock();
+        if self.mark_closed(&mut inner) {
+            drop(inner);
+            self.notify_closed();
+        }
+    }
+
+    // Returns whether the channel wasn't closed already, in which case the
+    // caller owes a `notify_closed` once the lock is released.
+    fn mark_closed(&self, inner: &mut Inner<T>) -> bool {
+        if inner.closed {
+            return false;
+        }
+        inner.closed = true;
+        event!(debug, self, "closed");
+        inner.wake_receivers();
+        inner.wake_senders();
+        true
+    }
+
+    fn notify_closed(&self) {
+        self.available.notify_all();
+        self.space.notify_all();
+        self.closing.notify_all();
+    }
+
+    // Appends the whole queue to `drained`, in the order it would have been
+    // received.
+    fn drain(&self, drained: VecDeque<T>) -> VecDeque<T> {
+        let mut inner = self.inner.lock();
+        if inner.paused {
+            drop(inner);
+            self.record_received(drained.len());
+            return drained;
+        }
+        let before = drained.len();
+        let drained = self.drain_locked(&mut inner, drained);
+        let freed = drained.len() > before && inner.is_bounded();
+        drop(inner);
+        if freed {
+            self.space.notify_all();
+        }
+        self.record_received(drained.len());
+        drained
+    }
+
+    // The part of `drain` done under the lock; notifying `space` and the
+    // metrics are left to the caller.
+    fn drain_locked(&self, inner: &mut Inner<T>, mut drained: VecDeque<T>) -> VecDeque<T> {
+        self.expire(inner);
+        inner.clear_stamps();
+        inner.bytes = 0;
+        let taken = inner.queue.len();
+        if inner.lifo {
+            drained.extend(inner.queue.drain(..).rev());
+        } else if drained.is_empty() {
+            core::mem::swap(&mut drained, &mut inner.queue);
+        } else {
+            drained.append(&mut inner.queue);
+        }
+        inner.taken += taken as u64;
+        if taken > 0 && inner.is_bounded() {
+            inner.wake_senders();
+        }
+        inner.lower_signal();
+        drained
+    }
+
+    fn push(&self, inner: &mut Inner<T>, t: T) {
+        self.push_at(inner, t, false);
+    }
+
+    // With `front` set, `t` is queued to be received next.
+    fn push_at(&self, inner: &mut Inner<T>, t: T, front: bool) {
+        if front {
+            inner.push_next(t);
+        } else {
+            inner.push(t);
+        }
+        event!(trace, self, depth = inner.queue.len(), "send");
+        if let Some(metrics) = &self.metrics {
+            metrics.sent.fetch_add(1, Ordering::Relaxed);
+            let depth = inner.queue.len() + self.buffered.load(Ordering::Relaxed);
+            metrics.peak_depth.fetch_max(depth, Ordering::Relaxed);
+        }
+    }
+
+    // Gives a sender that is about to publish the chance to do so before we
+    // pay for a condvar wait. Yielding only starts halfway, once a plain spin
+    // hasn't paid off.
+    fn spin(&self) {
+        for i in 0..self.spin {
+            if i < self.spin / 2 {
+                core::hint::spin_loop();
+            } else {
+                #[cfg(feature = "std")]
+                std::thread::yield_now();
+                #[cfg(not(feature = "std"))]
+                core::hint::spin_loop();
+            }
+            if let Some(inner) = self.inner.try_lock() {
+                if !inner.queue.is_empty() || inner.exhausted() {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn record_received(&self, n: usize) {
+        if n > 0 {
+            event!(trace, self, count = n, "recv");
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.received.fetch_add(n as u64, Ordering::Relaxed);
+        }
+    }
+
+    fn stats(&self) -> Option<ChannelStats> {
+        let metrics = self.metrics.as_ref()?;
+        let inner = self.inner.lock();
+        Some(ChannelStats {
+            sent: metrics.sent.load(Ordering::Relaxed),
+            received: metrics.received.load(Ordering::Relaxed),
+            dropped: inner.dropped,
+            expired: inner.expired,
+            depth: inner.queue.len() + self.buffered.load(Ordering::Relaxed),
+            peak_depth: metrics.peak_depth.load(Ordering::Relaxed),
+        })
+    }
+
+    fn send_until(&self, t: T, deadline: Option<Instant>) -> Result<(), SendTimeoutError<T>> {
+        let mut inner = self.inner.lock();
+        let mut ticket = None;
+        loop {
+            if let Some(refusal) = inner.refusal() {
+                self.leave_queue(&mut inner, ticket);
+                return Err(refusal.send_timeout(t));
+            }
+            if inner.is_next(ticket) {
+                if !inner.lacks_room(&t) {
+                    break;
+                }
+                match inner.overflow(&t) {
+                    Some(true) => break,
+                    Some(false) => return Ok(()),
+                    None if inner.policy == OverflowPolicy::Reject => {
+                        return Err(SendTimeoutError::Timeout(t));
+                    }
+                    None => {}
+                }
+            }
+            ticket = ticket.or_else(|| Some(inner.join_queue()));
+            event!(debug, self, depth = inner.queue.len(), "send blocking");
+            let (guard, timed_out) = wait_until(&self.space, inner, deadline);
+            inner = guard;
+            if timed_out {
+                self.leave_queue(&mut inner, ticket);
+                return Err(SendTimeoutError::Timeout(t));
+            }
+        }
+        self.leave_queue(&mut inner, ticket);
+        self.push(&mut inner, t);
+        if !inner.defers_wake() {
+            inner.wake_receivers();
+            self.available.notify_one();
+        }
+        if inner.capacity != Some(0) {
+            return Ok(());
+        }
+
+        // Rendezvous: the message only counts as sent once a receiver took it.
+        let handoff = inner.taken + 1;
+        while inner.taken < handoff {
+            let disconnected = inner.receivers == 0;
+            let (guard, timed_out) = if disconnected {
+                (inner, false)
+            } else {
+                wait_until(&self.space, inner, deadline)
+            };
+            inner = guard;
+            if inner.taken >= handoff {
+                break;
+            }
+            if disconnected || timed_out {
+                let t = inner.pop_newest().expect("rendezvous message was taken");
+                drop(inner);
+                self.space.notify_all();
+                return Err(if disconnected {
+                    SendTimeoutError::Disconnected(t)
+                } else {
+                    SendTimeoutError::Timeout(t)
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // A sender that got its turn, or gave up, lets the next one in line
+    // check for room.
+    fn leave_queue(&self, inner: &mut Inner<T>, ticket: Option<u64>) {
+        let Some(ticket) = ticket else {
+            return;
+        };
+        inner.send_queue.retain(|&t| t != ticket);
+        if !inner.send_queue.is_empty() {
+            self.space.notify_all();
+        } else {
+            // Async senders hold back while anyone is in line.
+            inner.wake_senders();
+        }
+    }
+
+    // Blocked senders go in the order they arrived, so only the whole queue
+    // can be sure to wake the one whose turn it is.
+    fn notify_space(&self, inner: &Inner<T>) {
+        if inner.send_queue.is_empty() {
+            self.space.notify_one();
+        } else {
+            self.space.notify_all();
+        }
+    }
+
+    fn take(&self, inner: &mut Inner<T>, buffer: &mut VecDeque<T>) -> Option<T> {
+        self.take_timed(inner, buffer).map(|(t, _)| t)
+    }
+
+    // Also returns how long the message sat in the queue and its sequence
+    // number, for channels that track them.
+    fn take_timed(&self, inner: &mut Inner<T>, buffer: &mut VecDeque<T>) -> Option<(T, Stamp)> {
+        self.expire(inner);
+        let Some((t, stamp)) = inner.pop_received() else {
+            inner.lower_signal();
+            return None;
+        };
+        inner.taken += 1;
+        self.record_received(1);
+        if inner.is_bounded() {
+            inner.wake_senders();
+        }
+        match inner.capacity {
+            // Blocked rendezvous senders wait on either a free slot or their own
+            // handoff, so all of them need to re-check.
+            Some(0) => self.space.notify_all(),
+            // The freed bytes may fit several smaller messages.
+            _ if inner.budget.is_some() => self.space.notify_all(),
+            // Swapping would move the whole queue out from under the bound, so
+            // bounded channels hand out one message at a time instead.
+            Some(_) => self.notify_space(inner),
+            // Same for competing receivers: one of them draining the queue into
+            // its private buffer would starve the others. Send times and
+            // numbers only live in the shared queue, so channels tracking them
+            // don't swap either, and nor do stacks, whose next message may not
+            // be sent yet.
+            None if inner.receivers > 1 || inner.stamped || inner.sequenced || inner.lifo => {}
+            None => {
+                core::mem::swap(buffer, &mut inner.queue);
+                self.buffered.fetch_add(buffer.len(), Ordering::Relaxed);
+            }
+        }
+        Some((t, stamp))
+    }
+
+    // Expired messages count as taken, so a rendezvous sender whose message
+    // timed out in the handoff slot stops waiting.
+    #[cfg(feature = "std")]
+    fn expire(&self, inner: &mut Inner<T>) {
+        let Some(ttl) = inner.ttl else {
+            return;
+        };
+        let now = Instant::now();
+        let mut expired = false;
+        while inner.sent_at.front().is_some_and(|&at| now >= at + ttl) {
+            let t = inner.pop().expect("every queued message has a timestamp");
+            inner.taken += 1;
+            inner.expired += 1;
+            expired = true;
+            if let Some(dead_letter) = &mut inner.dead_letter {
+                dead_letter(t);
+            }
+        }
+        if expired && inner.is_bounded() {
+            inner.wake_senders();
+            self.space.notify_all();
+        }
+    }
+
+    // Nothing can be given a TTL without a clock.
+    #[cfg(not(feature = "std"))]
+    fn expire(&self, _inner: &mut Inner<T>) {}
+}
+
+#[cfg(feature = "std")]
+fn wait_until<'a, U>(
+    cv: &Condvar,
+    guard: MutexGuard<'a, U>,
+    deadline: Option<Instant>,
+) -> (MutexGuard<'a, U>, bool) {
+    match deadline {
+        None => (cv.wait(guard), false),
+        Some(deadline) => {
+            let now = Instant::now();
+            if now >= deadline {
+                return (guard, true);
+            }
+            (cv.wait_timeout(guard, deadline - now), false)
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+fn wait_until<'a, U>(
+    cv: &Condvar,
+    guard: MutexGuard<'a, U>,
+    deadline: Option<Instant>,
+) -> (MutexGuard<'a, U>, bool) {
+    match deadline {
+        None => (cv.wait(guard), false),
+        Some(deadline) => match deadline {},
+    }
+}
+
+struct Inner<T> {
+    queue: VecDeque<T>,
+    preallocate: usize,
+    // Set once the last sender is gone.
+    disconnected: bool,
+    weak_senders: usize,
+    receivers: usize,
+    capacity: Option<usize>,
+    policy: OverflowPolicy,
+    dropped: u64,
+    expired: u64,
+    #[cfg(feature = "std")]
+    ttl: Option<Duration>,
+    latency: Option<LatencyStats>,
+    // Send times of the queued messages, tracked only for TTL and latency.
+    stamped: bool,
+    sent_at: VecDeque<Instant>,
+    // Sequence numbers of the queued messages, likewise only if asked for.
+    sequenced: bool,
+    seqs: VecDeque<u64>,
+    next_seq: u64,
+    // These run with the channel lock held, so they must not use this
+    // channel.
+    #[cfg(feature = "std")]
+    dead_letter: Option<DeadLetter<T>>,
+    on_drop: Option<DeadLetter<T>>,
+    budget: Option<ByteBudget<T>>,
+    // Estimated size of the queued messages, tracked only under a budget.
+    bytes: usize,
+    closed: bool,
+    waiting: usize,
+    taken: u64,
+    // Slots held by outstanding permits.
+    reserved: usize,
+    paused: bool,
+    lifo: bool,
+    // Tickets of the senders blocked on a full channel, oldest first.
+    send_queue: VecDeque<u64>,
+    next_ticket: u64,
+    recv_waiters: WaitList,
+    send_waiters: WaitList,
+    #[cfg(all(feature = "std", unix))]
+    signal: Option<fd::Signal>,
+    #[cfg(feature = "std")]
+    wake_threshold: Option<WakeThreshold>,
+    // When the oldest message the receiver hasn't been woken for arrived.
+    #[cfg(feature = "std")]
+    pending_since: Option<Instant>,
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+struct WakeThreshold {
+    count: usize,
+    max_delay: Duration,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Stamp {
+    latency: Option<Duration>,
+    seq: u64,
+}
+
+type DeadLetter<T> = Box<dyn FnMut(T) + Send>;
+
+struct ByteBudget<T> {
+    limit: usize,
+    size_of: Box<dyn Fn(&T) -> usize + Send>,
+}
+
+impl<T> Inner<T> {
+    fn push(&mut self, t: T) {
+        #[cfg(feature = "std")]
+        if self.stamped {
+            self.sent_at.push_back(Instant::now());
+        }
+        #[cfg(feature = "std")]
+        if self.wake_threshold.is_some() && self.queue.is_empty() {
+            self.pending_since = Some(Instant::now());
+        }
+        if self.sequenced {
+            let seq = self.take_seq();
+            self.seqs.push_back(seq);
+        }
+        if let Some(budget) = &self.budget {
+            self.bytes += (budget.size_of)(&t);
+        }
+        self.queue.push_back(t);
+    }
+
+    // Whether `push` records anything alongside the message itself.
+    fn tracks_pushes(&self) -> bool {
+        #[cfg(feature = "std")]
+        if self.wake_threshold.is_some() {
+            return true;
+        }
+        self.stamped || self.sequenced
+    }
+
+    // Queues `t` to be received before anything else, with a fresh
+    // timestamp as the original one is gone.
+    fn push_next(&mut self, t: T) {
+        if self.lifo {
+            self.push(t);
+            return;
+        }
+        if let Some(budget) = &self.budget {
+            self.bytes += (budget.size_of)(&t);
+        }
+        #[cfg(feature = "std")]
+        if self.stamped {
+            self.sent_at.push_front(Instant::now());
+        }
+        #[cfg(feature = "std")]
+        if self.wake_threshold.is_some() && self.queue.is_empty() {
+            self.pending_since = Some(Instant::now());
+        }
+        if self.sequenced {
+            let seq = self.take_seq();
+            self.seqs.push_front(seq);
+        }
+        self.queue.push_front(t);
+    }
+
+    fn take_seq(&mut self) -> u64 {
+        self.next_seq += 1;
+        self.next_seq
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.sent_at.pop_front();
+        self.seqs.pop_front();
+        let t = self.queue.pop_front()?;
+        self.release(&t);
+        Some(t)
+    }
+
+    fn pop_received(&mut self) -> Option<(T, Stamp)> {
+        if self.paused {
+            return None;
+        }
+        let (sent_at, seq, t) = if self.lifo {
+            let t = self.queue.pop_back()?;
+            (self.sent_at.pop_back(), self.seqs.pop_back(), t)
+        } else {
+            let t = self.queue.pop_front()?;
+            (self.sent_at.pop_front(), self.seqs.pop_front(), t)
+        };
+        self.release(&t);
+        let latency = sent_at.map(|at| at.elapsed());
+        if let (Some(stats), Some(latency)) = (&mut self.latency, latency) {
+            stats.record(latency);
+        }
+        let seq = seq.unwrap_or_default();
+        Some((t, Stamp { latency, seq }))
+    }
+
+    // The whole queue is being handed out at once.
+    fn clear_stamps(&mut self) {
+        #[cfg(feature = "std")]
+        if let Some(stats) = &mut self.latency {
+            let now = Instant::now();
+            for at in &self.sent_at {
+                stats.record(now - *at);
+            }
+        }
+        self.sent_at.clear();
+        self.seqs.clear();
+    }
+
+    fn pop_newest(&mut self) -> Option<T> {
+        self.sent_at.pop_back();
+        self.seqs.pop_back();
+        let t = self.queue.pop_back()?;
+        self.release(&t);
+        Some(t)
+    }
+
+    fn release(&mut self, t: &T) {
+        if let Some(budget) = &self.budget {
+            self.bytes = self.bytes.saturating_sub((budget.size_of)(t));
+        }
+    }
+
+    fn is_bounded(&self) -> bool {
+        self.capacity.is_some() || self.budget.is_some()
+    }
+
+    // A message bigger than the whole budget still goes through on its own.
+    fn lacks_room(&self, t: &T) -> bool {
+        match &self.budget {
+            Some(budget) if !self.queue.is_empty() => {
+                self.is_full() || self.bytes + (budget.size_of)(t) > budget.limit
+            }
+            _ => self.is_full(),
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        match self.capacity {
+            // A rendezvous channel holds at most the one message being handed off.
+            Some(0) => !self.queue.is_empty(),
+            Some(cap) => self.queue.len() + self.reserved >= cap,
+            None => false,
+        }
+    }
+
+    // Whether every one of `items` can be queued without overflowing.
+    fn fits(&self, items: &[T]) -> bool {
+        let slots = match self.capacity {
+            // Nothing waits in a rendezvous channel.
+            Some(0) => false,
+            Some(cap) => self.queue.len() + self.reserved + items.len() <= cap,
+            None => true,
+        };
+        let bytes = match &self.budget {
+            Some(budget) => {
+                let size: usize = items.iter().map(|t| (budget.size_of)(t)).sum();
+                self.bytes + size <= budget.limit
+            }
+            None => true,
+        };
+        slots && bytes
+    }
+
+    // Whether a sender holding `ticket` may take free room now. Newcomers
+    // don't get to pass senders that are already waiting.
+    fn is_next(&self, ticket: Option<u64>) -> bool {
+        match ticket {
+            Some(ticket) => self.send_queue.front() == Some(&ticket),
+            None => self.send_queue.is_empty(),
+        }
+    }
+
+    fn join_queue(&mut self) -> u64 {
+        let ticket = self.next_ticket;
+        self.next_ticket += 1;
+        self.send_queue.push_back(ticket);
+        ticket
+    }
+
+    fn is_full_nonblocking(&self, t: &T) -> bool {
+        match self.capacity {
+            // A rendezvous channel only has room when a receiver is already
+            // parked waiting for a message and nobody else is mid-handoff.
+            Some(0) => self.waiting == 0 || !self.queue.is_empty(),
+            _ => self.lacks_room(t),
+        }
+    }
+
+    // Makes room in a full channel according to its policy. Returns whether
+    // the new message should still be queued, or `None` if the sender has to
+    // wait or give up.
+    fn overflow(&mut self, t: &T) -> Option<bool> {
+        if self.paused {
+            return None;
+        }
+        match self.policy {
+            OverflowPolicy::Block | OverflowPolicy::Reject => None,
+            OverflowPolicy::DropNewest => {
+                self.dropped += 1;
+                // Leave a gap where the message would have been.
+                if self.sequenced {
+                    self.take_seq();
+                }
+                Some(false)
+            }
+            OverflowPolicy::DropOldest => {
+                // Under a byte budget one eviction may not be enough.
+                while self.lacks_room(t) && self.pop().is_some() {
+                    self.dropped += 1;
+                }
+                Some(true)
+            }
+        }
+    }
+
+    fn refusal(&self) -> Option<Refusal> {
+        if self.receivers == 0 {
+            Some(Refusal::Disconnected)
+        } else if self.closed {
+            Some(Refusal::Closed)
+        } else {
+            None
+        }
+    }
+
+    // Nothing more will ever be queued once every sender is gone or the
+    // receiving side closed the channel.
+    fn exhausted(&self) -> bool {
+        self.disconnected || self.closed
+    }
+
+    // Receivers give up on an exhausted channel, but a paused one keeps them
+    // waiting for `resume` with whatever is still queued.
+    fn ends_receiving(&self) -> bool {
+        self.exhausted() && !self.paused
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        // Sinks may overshoot the bound slightly, hence the saturation.
+        self.capacity
+            .map(|cap| cap.saturating_sub(self.queue.len() + self.reserved))
+    }
+
+    // The first message always wakes the receiver, which then goes back to
+    // sleep until the batch is complete, so later ones needn't.
+    #[cfg(feature = "std")]
+    fn defers_wake(&self) -> bool {
+        match self.wake_threshold {
+            Some(threshold) => {
+                self.queue.len() > 1 && self.queue.len() < threshold.count && !self.exhausted()
+            }
+            None => false,
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn defers_wake(&self) -> bool {
+        false
+    }
+
+    // Until when a blocking receiver should leave the queued messages be.
+    #[cfg(feature = "std")]
+    fn batch_deadline(&self) -> Option<Instant> {
+        let threshold = self.wake_threshold?;
+        if self.queue.is_empty()
+            || self.queue.len() >= threshold.count
+            || self.paused
+            || self.exhausted()
+        {
+            return None;
+        }
+        let due = self.pending_since? + threshold.max_delay;
+        (Instant::now() < due).then_some(due)
+    }
+
+    fn wake_receivers(&mut self) {
+        self.recv_waiters.wake_all();
+        #[cfg(all(feature = "std", unix))]
+        if let Some(signal) = &mut self.signal {
+            signal.raise();
+        }
+    }
+
+    fn wake_senders(&mut self) {
+        self.send_waiters.wake_all();
+    }
+
+    // Drops readiness once nothing is left to receive. A channel that is done
+    // stays readable for good.
+    fn lower_signal(&mut self) {
+        #[cfg(all(feature = "std", unix))]
+        if (self.queue.is_empty() || self.paused) && !self.ends_receiving() {
+            if let Some(signal) = &mut self.signal {
+                signal.lower();
+            }
+        }
+    }
+}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    ChannelBuilder::new().build()
+}
+
+pub fn sync_channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    ChannelBuilder::new().capacity(capacity).build()
+}
+
+pub fn sync_channel_with_policy<T>(
+    capacity: usize,
+    policy: OverflowPolicy,
+) -> (Sender<T>, Receiver<T>) {
+    ChannelBuilder::new()
+        .capacity(capacity)
+        .overflow(policy)
+        .build()
+}
+
+pub struct ChannelBuilder<T> {
+    capacity: Option<usize>,
+    policy: OverflowPolicy,
+    ttl: Option<Duration>,
+    dead_letter: Option<DeadLetter<T>>,
+    on_drop: Option<DeadLetter<T>>,
+    budget: Option<ByteBudget<T>>,
+    preallocate: usize,
+    metrics: bool,
+    track_latency: bool,
+    sequence_numbers: bool,
+    spin: u32,
+    pollable: bool,
+    lifo: bool,
+    #[cfg(feature = "std")]
+    wake_threshold: Option<WakeThreshold>,
+    name: Option<Box<str>>,
+}
+
+impl<T> ChannelBuilder<T> {
+    pub fn new() -> Self {
+        ChannelBuilder {
+            capacity: None,
+            policy: OverflowPolicy::Block,
+            ttl: None,
+            dead_letter: None,
+            on_drop: None,
+            budget: None,
+            preallocate: 0,
+            metrics: false,
+            track_latency: false,
+            sequence_numbers: false,
+            spin: 0,
+            pollable: false,
+            lifo: false,
+            #[cfg(feature = "std")]
+            wake_threshold: None,
+            name: None,
+        }
+    }
+
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    // Only consulted by channels with a capacity or a byte budget.
+    pub fn overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    // Bounds the channel by the estimated size of its queued messages rather
+    // than, or on top of, their count.
+    pub fn max_bytes<F: Fn(&T) -> usize + Send + 'static>(
+        mut self,
+        limit: usize,
+        size_of: F,
+    ) -> Self {
+        self.budget = Some(ByteBudget {
+            limit,
+            size_of: Box::new(size_of),
+        });
+        self
+    }
+
+    // Reserves room for `n` messages up front, both in the queue and in the
+    // receiver's buffer it gets swapped with.
+    pub fn preallocate(mut self, n: usize) -> Self {
+        self.preallocate = n;
+        self
+    }
+
+    // Timestamps every message so receivers can see how long it was queued.
+    #[cfg(feature = "std")]
+    pub fn track_latency(mut self) -> Self {
+        self.track_latency = true;
+        self
+    }
+
+    // Numbers every message for `recv_with_seq`.
+    pub fn sequence_numbers(mut self) -> Self {
+        self.sequence_numbers = true;
+        self
+    }
+
+    // Tags the channel's `tracing` events.
+    pub fn name(mut self, name: impl Into<Box<str>>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    // Makes `stats` on either handle return counters.
+    pub fn metrics(mut self) -> Self {
+        self.metrics = true;
+        self
+    }
+
+    // An empty receive polls up to `limit` times, spinning and then yielding,
+    // before it parks on the condvar.
+    pub fn spin(mut self, limit: u32) -> Self {
+        self.spin = limit;
+        self
+    }
+
+    // Gives receivers a file descriptor, via `AsFd`, that is readable while
+    // there may be something to receive.
+    #[cfg(all(feature = "std", unix))]
+    pub fn pollable(mut self) -> Self {
+        self.pollable = true;
+        self
+    }
+
+    // Delivers the newest queued message first. Overflow policies and TTLs
+    // still measure age the usual way, so `DropOldest` evicts from the bottom
+    // of the stack.
+    pub fn lifo(mut self) -> Self {
+        self.lifo = true;
+        self
+    }
+
+    // Lets messages pile up before a blocked receiver is woken: it sleeps
+    // until `count` are queued or the oldest has waited `max_delay`. Only
+    // blocking receives wait for the batch; `try_recv` and async receivers
+    // take what's there.
+    #[cfg(feature = "std")]
+    pub fn wake_threshold(mut self, count: usize, max_delay: Duration) -> Self {
+        self.wake_threshold = Some(WakeThreshold { count, max_delay });
+        self
+    }
+
+    // Messages still queued `ttl` after they were sent are skipped by the
+    // receiver.
+    #[cfg(feature = "std")]
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    #[cfg(feature = "std")]
+    pub fn dead_letter<F: FnMut(T) + Send + 'static>(mut self, f: F) -> Self {
+        self.dead_letter = Some(Box::new(f));
+        self
+    }
+
+    // Called for every message thrown away undelivered: still queued or
+    // buffered when the last receiver went away, or sent through a permit
+    // after the channel closed. Runs with the channel lock held, so it must not use
+    // this channel.
+    pub fn on_drop<F: FnMut(T) + Send + 'static>(mut self, f: F) -> Self {
+        self.on_drop = Some(Box::new(f));
+        self
+    }
+
+    // Panics if a `pollable` channel's socket can't be created; `try_build`
+    // reports that instead.
+    pub fn build(self) -> (Sender<T>, Receiver<T>) {
+        // A rendezvous channel has no queue to drop from.
+        assert!(
+            self.capacity != Some(0) || self.policy == OverflowPolicy::Block,
+            "a rendezvous channel can only block"
+        );
+        let inner = Inner {
+            queue: VecDeque::with_capacity(self.preallocate),
+            preallocate: self.preallocate,
+            disconnected: false,
+            weak_senders: 0,
+            receivers: 1,
+            capacity: self.capacity,
+            policy: self.policy,
+            dropped: 0,
+            expired: 0,
+            #[cfg(feature = "std")]
+            ttl: self.ttl,
+            latency: self.track_latency.then(LatencyStats::default),
+            stamped: self.ttl.is_some() || self.track_latency,
+            sent_at: VecDeque::new(),
+            sequenced: self.sequence_numbers,
+            seqs: VecDeque::new(),
+            next_seq: 0,
+            #[cfg(feature = "std")]
+            dead_letter: self.dead_letter,
+            on_drop: self.on_drop,
+            budget: self.budget,
+            bytes: 0,
+            closed: false,
+            waiting: 0,
+            taken: 0,
+            reserved: 0,
+            paused: false,
+            lifo: self.lifo,
+            send_queue: VecDeque::new(),
+            next_ticket: 0,
+            recv_waiters: WaitList::new(),
+            send_waiters: WaitList::new(),
+            #[cfg(all(feature = "std", unix))]
+            signal: self
+                .pollable
+                .then(|| fd::Signal::new().expect("failed to create readiness socket")),
+            #[cfg(feature = "std")]
+            wake_threshold: self.wake_threshold,
+            #[cfg(feature = "std")]
+            pending_since: None,
+        };
+
+        let shared = Shared {
+            inner: CachePadded::new(Mutex::new(inner)),
+            available: CachePadded::new(Condvar::new()),
+            space: CachePadded::new(Condvar::new()),
+            closing: Condvar::new(),
+            buffered: CachePadded::new(AtomicUsize::new(0)),
+            senders: CachePadded::new(AtomicUsize::new(1)),
+            metrics: self.metrics.then(Metrics::default),
+            spin: self.spin,
+            name: self.name,
+        };
+
+        let shared = Arc::new(shared);
+
+        (
+            Sender {
+                shared: shared.clone(),
+            },
+            Receiver {
+                shared: shared.clone(),
+                buffer: VecDeque::with_capacity(self.preallocate),
+            },
+        )
+    }
+
+    // Like `build`, but running out of file descriptors for a `pollable`
+    // channel's socket is an error rather than a panic.
+    #[cfg(feature = "std")]
+    pub fn try_build(mut self) -> std::io::Result<(Sender<T>, Receiver<T>)> {
+        #[cfg(unix)]
+        let signal = match core::mem::take(&mut self.pollable) {
+            true => Some(fd::Signal::new()?),
+            false => None,
+        };
+        let (tx, rx) = self.build();
+        #[cfg(unix)]
+        {
+            tx.shared.inner.lock().signal = signal;
+        }
+        Ok((tx, rx))
+    }
+}
+
+impl<T> fmt::Debug for ChannelBuilder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("ChannelBuilder");
+        d.field("capacity", &self.capacity)
+            .field("policy", &self.policy)
+            .field("ttl", &self.ttl)
+            .field("dead_letter", &self.dead_letter.is_some())
+            .field("on_drop", &self.on_drop.is_some())
+            .field("max_bytes", &self.budget.as_ref().map(|b| b.limit))
+            .field("preallocate", &self.preallocate)
+            .field("metrics", &self.metrics)
+            .field("track_latency", &self.track_latency)
+            .field("sequence_numbers", &self.sequence_numbers)
+            .field("spin", &self.spin)
+            .field("pollable", &self.pollable)
+            .field("lifo", &self.lifo);
+        #[cfg(feature = "std")]
+        d.field("wake_threshold", &self.wake_threshold);
+        d.field("name", &self.name).finish()
+    }
+}
+
+impl<T> Default for ChannelBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn single_sender_test() {
+        let (tx, mut rx) = channel::<i32>();
+        tx.send(43).unwrap();
+        assert_eq!(rx.recv(), Some(43));
+    }
+
+    #[test]
+    fn single_sender_drop_test() {
+        let (tx, mut rx) = channel::<i32>();
+        drop(tx);
+        assert_eq!(rx.recv(), None);
+    }
+
+    #[test]
+    fn multi_send_test() {
+        let (tx, mut rx) = channel::<i32>();
+        let tx2 = tx.clone();
+        thread::spawn(move || {
+            tx.send(1).unwrap();
+        });
+
+        thread::spawn(move || tx2.send(2).unwrap());
+
+        let f1 = rx.recv();
+        let f2 = rx.recv();
+        let end = rx.recv();
+        assert_eq!(f1, Some(1));
+        assert_eq!(f2, Some(2));
+        assert_eq!(end, None);
+    }
+
+    #[test]
+    fn try_recv_test() {
+        let (tx, mut rx) = channel::<i32>();
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(rx.try_recv(), Ok(1));
+        drop(tx);
+        assert_eq!(rx.try_recv(), Ok(2));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn recv_timeout_test() {
+        let (tx, mut rx) = channel::<i32>();
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            tx.send(7).unwrap();
+        });
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(7));
+        handle.join().unwrap();
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn recv_deadline_test() {
+        let (tx, mut rx) = channel::<i32>();
+        let start = Instant::now();
+        let deadline = start + Duration::from_millis(20);
+        assert_eq!(rx.recv_deadline(deadline), Err(RecvTimeoutError::Timeout));
+        assert!(Instant::now() >= deadline);
+        tx.send(3).unwrap();
+        assert_eq!(rx.recv_deadline(start), Ok(3));
+    }
+
+    #[test]
+    fn sync_channel_backpressure_test() {
+        let (tx, mut rx) = sync_channel::<i32>(2);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        let handle = thread::spawn(move || {
+            tx.send(3).unwrap();
+        });
+        thread::sleep(Duration::from_millis(20));
+        assert!(!handle.is_finished());
+        assert_eq!(rx.recv(), Some(1));
+        handle.join().unwrap();
+        assert_eq!(rx.recv(), Some(2));
+        assert_eq!(rx.recv(), Some(3));
+        assert_eq!(rx.recv(), None);
+    }
+
+    #[test]
+    fn sync_channel_receiver_drop_test() {
+        let (tx, rx) = sync_channel::<i32>(1);
+        tx.send(1).unwrap();
+        let handle = thread::spawn(move || tx.send(2));
+        thread::sleep(Duration::from_millis(10));
+        drop(rx);
+        assert_eq!(handle.join().unwrap(), Err(SendError::Disconnected(2)));
+    }
+
+    #[test]
+    fn try_send_test() {
+        let (tx, mut rx) = sync_channel::<i32>(1);
+        assert_eq!(tx.try_send(1), Ok(()));
+        assert_eq!(tx.try_send(2), Err(TrySendError::Full(2)));
+        assert_eq!(rx.recv(), Some(1));
+        assert_eq!(tx.try_send(3), Ok(()));
+        drop(rx);
+        assert_eq!(tx.try_send(4), Err(TrySendError::Disconnected(4)));
+    }
+
+    #[test]
+    fn send_timeout_test() {
+        let (tx, mut rx) = sync_channel::<i32>(1);
+        assert_eq!(tx.send_timeout(1, Duration::from_millis(10)), Ok(()));
+        assert_eq!(
+            tx.send_timeout(2, Duration::from_millis(10)),
+            Err(SendTimeoutError::Timeout(2))
+        );
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            assert_eq!(rx.recv(), Some(1));
+            rx
+        });
+        assert_eq!(tx.send_timeout(3, Duration::from_secs(5)), Ok(()));
+        drop(handle.join().unwrap());
+        assert_eq!(
+            tx.send_timeout(4, Duration::from_millis(10)),
+            Err(SendTimeoutError::Disconnected(4))
+        );
+    }
+
+    #[test]
+    fn rendezvous_test() {
+        let (tx, mut rx) = sync_channel::<i32>(0);
+        assert_eq!(tx.try_send(1), Err(TrySendError::Full(1)));
+        assert_eq!(
+            tx.send_timeout(1, Duration::from_millis(10)),
+            Err(SendTimeoutError::Timeout(1))
+        );
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+
+        let handle = thread::spawn(move || {
+            tx.send(2).unwrap();
+            tx
+        });
+        thread::sleep(Duration::from_millis(20));
+        assert!(!handle.is_finished());
+        assert_eq!(rx.recv(), Some(2));
+        let tx = handle.join().unwrap();
+
+        let handle = thread::spawn(move || rx.recv());
+        while tx.try_send(3).is_err() {
+            thread::yield_now();
+        }
+        assert_eq!(handle.join().unwrap(), Some(3));
+        assert_eq!(
+            tx.send_timeout(4, Duration::from_secs(5)),
+            Err(SendTimeoutError::Disconnected(4))
+        );
+    }
+
+    #[test]
+    fn send_after_receiver_drop_test() {
+        let (tx, rx) = channel::<i32>();
+        tx.send(1).unwrap();
+        drop(rx);
+        assert_eq!(tx.send(2), Err(SendError::Disconnected(2)));
+    }
+
+    #[test]
+    fn is_closed_test() {
+        let (tx, rx) = channel::<i32>();
+        let tx2 = tx.clone();
+        assert!(!tx.is_closed());
+        drop(rx);
+        assert!(tx.is_closed());
+        assert!(tx2.is_closed());
+    }
+
+    #[test]
+    fn shared_sender_test() {
+        fn assert_sync<S: Sync>(_: &S) {}
+        let (tx, rx) = channel::<i32>();
+        assert_sync(&tx);
+        thread::scope(|s| {
+            s.spawn(|| tx.send(1).unwrap());
+            s.spawn(|| tx.send(2).unwrap());
+        });
+        drop(tx);
+        let mut got: Vec<_> = rx.collect();
+        got.sort();
+        assert_eq!(got, vec![1, 2]);
+    }
+
+    #[test]
+    fn multi_receiver_test() {
+        let (tx, rx) = channel::<i32>();
+        let workers: Vec<_> = (0..3)
+            .map(|_| {
+                let rx = rx.clone();
+                thread::spawn(move || rx.collect::<Vec<_>>())
+            })
+            .collect();
+        drop(rx);
+        for i in 0..300 {
+            tx.send(i).unwrap();
+        }
+        drop(tx);
+        let mut got: Vec<_> = workers
+            .into_iter()
+            .flat_map(|w| w.join().unwrap())
+            .collect();
+        got.sort();
+        assert_eq!(got, (0..300).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn len_test() {
+        let (tx, mut rx) = channel::<i32>();
+        assert!(tx.is_empty() && rx.is_empty());
+        for i in 0..3 {
+            tx.send(i).unwrap();
+        }
+        assert_eq!(rx.len(), 3);
+        // The first recv swaps the rest of the queue into the receiver's buffer.
+        assert_eq!(rx.recv(), Some(0));
+        assert_eq!(tx.len(), 2);
+        assert_eq!(rx.len(), 2);
+        tx.send(3).unwrap();
+        assert_eq!(tx.len(), 3);
+        rx.recv().unwrap();
+        rx.recv().unwrap();
+        rx.recv().unwrap();
+        assert!(tx.is_empty());
+    }
+
+    #[test]
+    fn capacity_test() {
+        let (tx, rx) = sync_channel::<i32>(2);
+        assert_eq!(tx.capacity(), Some(2));
+        assert_eq!(rx.remaining(), Some(2));
+        tx.send(1).unwrap();
+        assert_eq!(tx.remaining(), Some(1));
+        tx.send(2).unwrap();
+        assert_eq!(rx.remaining(), Some(0));
+
+        let (tx, rx) = channel::<i32>();
+        assert_eq!(tx.capacity(), None);
+        assert_eq!(rx.remaining(), None);
+    }
+
+    #[test]
+    fn peek_test() {
+        let (tx, mut rx) = channel::<i32>();
+        assert_eq!(
+            rx.peek_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(rx.peek(), Some(&1));
+        assert_eq!(rx.peek(), Some(&1));
+        assert_eq!(tx.len(), 2);
+        assert_eq!(rx.recv(), Some(1));
+        drop(tx);
+        assert_eq!(rx.peek(), Some(&2));
+        assert_eq!(rx.recv(), Some(2));
+        assert_eq!(rx.peek(), None);
+    }
+
+    #[test]
+    fn try_iter_test() {
+        let (tx, mut rx) = channel::<i32>();
+        assert_eq!(rx.try_iter().count(), 0);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![1, 2]);
+        tx.send(3).unwrap();
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn iter_test() {
+        let (tx, mut rx) = channel::<i32>();
+        for i in 0..5 {
+            tx.send(i).unwrap();
+        }
+        for i in rx.iter() {
+            if i == 2 {
+                break;
+            }
+        }
+        assert_eq!(rx.try_recv(), Ok(3));
+        drop(tx);
+        let rest: Vec<_> = rx.iter().collect();
+        assert_eq!(rest, vec![4]);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn drain_test() {
+        let (tx, mut rx) = sync_channel::<i32>(3);
+        for i in 0..3 {
+            tx.send(i).unwrap();
+        }
+        assert_eq!(rx.peek(), Some(&0));
+        assert_eq!(rx.drain(), VecDeque::from(vec![0, 1, 2]));
+        assert!(rx.is_empty());
+        assert_eq!(tx.remaining(), Some(3));
+        assert!(rx.drain().is_empty());
+    }
+
+    #[test]
+    fn recv_many_test() {
+        let (tx, mut rx) = sync_channel::<i32>(8);
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            for i in 0..5 {
+                tx.send(i).unwrap();
+            }
+        });
+        let mut buf = Vec::new();
+        let mut total = 0;
+        while total < 5 {
+            total += rx.recv_many(&mut buf, 3);
+        }
+        handle.join().unwrap();
+        assert_eq!(buf, vec![0, 1, 2, 3, 4]);
+        assert_eq!(rx.recv_many(&mut buf, 3), 0);
+        assert_eq!(rx.recv_many(&mut buf, 0), 0);
+    }
+
+    #[test]
+    fn send_all_test() {
+        let (tx, rx) = channel::<i32>();
+        tx.send(0).unwrap();
+        tx.send_all(vec![1, 2, 3]).unwrap();
+        assert_eq!(tx.len(), 4);
+        drop(tx);
+        assert_eq!(rx.collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+
+        let (tx, rx) = sync_channel::<i32>(2);
+        let handle = thread::spawn(move || rx.collect::<Vec<_>>());
+        tx.send_all(0..10).unwrap();
+        drop(tx);
+        assert_eq!(handle.join().unwrap(), (0..10).collect::<Vec<_>>());
+
+        let (tx, rx) = sync_channel::<i32>(1);
+        drop(rx);
+        assert_eq!(
+            tx.send_all(vec![1, 2]),
+            Err(SendError::Disconnected(vec![1, 2]))
+        );
+    }
+
+    #[test]
+    fn weak_sender_test() {
+        let (tx, mut rx) = channel::<i32>();
+        let weak = tx.downgrade();
+        weak.upgrade().unwrap().send(1).unwrap();
+        assert_eq!(rx.recv(), Some(1));
+        drop(tx);
+        assert_eq!(rx.recv(), None);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn same_channel_test() {
+        let (tx, _rx) = channel::<i32>();
+        let (other, _other_rx) = channel::<i32>();
+        assert!(tx.same_channel(&tx.clone()));
+        assert!(!tx.same_channel(&other));
+    }
+
+    #[test]
+    fn wait_closed_test() {
+        let (tx, rx) = sync_channel::<i32>(1);
+        let handle = thread::spawn(move || {
+            tx.wait_closed();
+            tx.is_closed()
+        });
+        thread::sleep(Duration::from_millis(10));
+        assert!(!handle.is_finished());
+        drop(rx);
+        assert!(handle.join().unwrap());
+    }
+
+    #[test]
+    fn close_test() {
+        let (tx, mut rx) = sync_channel::<i32>(1);
+        tx.send(1).unwrap();
+        let blocked = tx.clone();
+        let handle = thread::spawn(move || blocked.send(2));
+        thread::sleep(Duration::from_millis(10));
+        rx.close();
+        assert_eq!(handle.join().unwrap(), Err(SendError::Closed(2)));
+        assert_eq!(tx.try_send(3), Err(TrySendError::Closed(3)));
+        assert!(tx.is_closed());
+        assert_eq!(rx.recv(), Some(1));
+        assert_eq!(rx.recv(), None);
+    }
+
+    #[test]
+    fn overflow_policy_test() {
+        let (tx, rx) = sync_channel_with_policy::<i32>(2, OverflowPolicy::DropOldest);
+        for i in 0..5 {
+            tx.send(i).unwrap();
+        }
+        assert_eq!(tx.dropped(), 3);
+        drop(tx);
+        assert_eq!(rx.collect::<Vec<_>>(), vec![3, 4]);
+
+        let (tx, rx) = sync_channel_with_policy::<i32>(2, OverflowPolicy::DropNewest);
+        tx.send_all(0..5).unwrap();
+        assert_eq!(tx.try_send(5), Ok(()));
+        assert_eq!(rx.dropped(), 4);
+        drop(tx);
+        assert_eq!(rx.collect::<Vec<_>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn overflow_reject_test() {
+        let (tx, mut rx) = sync_channel_with_policy::<i32>(1, OverflowPolicy::Reject);
+        tx.send(1).unwrap();
+        assert_eq!(tx.send(2), Err(SendError::Full(2)));
+        assert_eq!(
+            tx.send_timeout(3, Duration::from_secs(1)),
+            Err(SendTimeoutError::Timeout(3))
+        );
+        assert_eq!(tx.dropped(), 0);
+        assert_eq!(rx.recv(), Some(1));
+        tx.send(4).unwrap();
+        assert_eq!(rx.recv(), Some(4));
+    }
+
+    #[test]
+    fn force_send_test() {
+        let (tx, mut rx) = sync_channel::<i32>(2);
+        assert_eq!(tx.force_send(1), Ok(None));
+        assert_eq!(tx.force_send(2), Ok(None));
+        assert_eq!(tx.force_send(3), Ok(Some(1)));
+        assert_eq!(rx.recv(), Some(2));
+        assert_eq!(rx.recv(), Some(3));
+
+        let (tx, rx) = sync_channel::<i32>(0);
+        assert_eq!(tx.force_send(1), Err(SendError::Full(1)));
+        drop(rx);
+        assert_eq!(tx.force_send(2), Err(SendError::Disconnected(2)));
+    }
+
+    #[test]
+    fn force_send_room_test() {
+        let (tx, mut rx) = sync_channel::<i32>(2);
+        let first = tx.reserve().unwrap();
+        let second = tx.reserve().unwrap();
+        assert_eq!(tx.force_send(1), Err(SendError::Full(1)));
+        drop(second);
+        tx.send(2).unwrap();
+        assert_eq!(tx.force_send(3), Ok(Some(2)));
+        first.send(4);
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![3, 4]);
+
+        let (tx, mut rx) = ChannelBuilder::new().max_bytes(10, Vec::len).build();
+        for _ in 0..3 {
+            tx.send(vec![0u8; 3]).unwrap();
+        }
+        assert_eq!(tx.force_send(vec![1; 8]), Ok(Some(vec![0; 3])));
+        assert_eq!(rx.dropped(), 2);
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![vec![1; 8]]);
+
+        let (tx, mut rx) = sync_channel::<i32>(1);
+        tx.send(1).unwrap();
+        rx.pause();
+        assert_eq!(tx.force_send(2), Err(SendError::Full(2)));
+    }
+
+    #[test]
+    fn ttl_test() {
+        let (dead_tx, dead_rx) = channel::<i32>();
+        let (tx, mut rx) = ChannelBuilder::new()
+            .ttl(Duration::from_millis(20))
+            .dead_letter(move |t| dead_tx.send(t).unwrap())
+            .build();
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        thread::sleep(Duration::from_millis(30));
+        tx.send(3).unwrap();
+        assert_eq!(rx.recv(), Some(3));
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(30)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        // The dead letter sender lives as long as the channel itself.
+        drop((tx, rx));
+        assert_eq!(dead_rx.collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn builder_test() {
+        let (tx, mut rx) = ChannelBuilder::<i32>::new()
+            .capacity(1)
+            .overflow(OverflowPolicy::DropNewest)
+            .build();
+        assert_eq!(tx.capacity(), Some(1));
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(rx.dropped(), 1);
+        assert_eq!(rx.recv(), Some(1));
+
+        let (tx, _rx) = ChannelBuilder::<i32>::default().build();
+        assert_eq!(tx.capacity(), None);
+    }
+
+    #[test]
+    fn preallocate_test() {
+        let (tx, mut rx) = ChannelBuilder::new().preallocate(1024).build();
+        assert!(tx.shared.inner.lock().queue.capacity() >= 1024);
+        assert!(rx.buffer.capacity() >= 1024);
+        tx.send_all(0..1000).unwrap();
+        assert!(tx.shared.inner.lock().queue.capacity() >= 1024);
+        assert_eq!(rx.recv(), Some(0));
+    }
+
+    #[test]
+    fn max_bytes_test() {
+        let (tx, mut rx) = ChannelBuilder::<Vec<u8>>::new()
+            .max_bytes(10, Vec::len)
+            .build();
+        tx.send(vec![0; 6]).unwrap();
+        assert_eq!(tx.try_send(vec![1; 6]), Err(TrySendError::Full(vec![1; 6])));
+        tx.send(vec![2; 4]).unwrap();
+        let handle = thread::spawn(move || tx.send(vec![3; 5]));
+        thread::sleep(Duration::from_millis(10));
+        assert!(!handle.is_finished());
+        assert_eq!(rx.recv().map(|v| v.len()), Some(6));
+        handle.join().unwrap().unwrap();
+        assert_eq!(rx.recv().map(|v| v.len()), Some(4));
+        assert_eq!(rx.recv().map(|v| v.len()), Some(5));
+    }
+
+    #[test]
+    fn stats_test() {
+        let (tx, mut rx) = ChannelBuilder::new()
+            .capacity(3)
+            .overflow(OverflowPolicy::DropNewest)
+            .metrics()
+            .build();
+        for i in 0..4 {
+            tx.send(i).unwrap();
+        }
+        assert_eq!(rx.peek(), Some(&0));
+        assert_eq!(rx.recv(), Some(0));
+        let stats = tx.stats().unwrap();
+        assert_eq!(
+            stats,
+            ChannelStats {
+                sent: 3,
+                received: 1,
+                dropped: 1,
+                expired: 0,
+                depth: 2,
+                peak_depth: 3,
+            }
+        );
+        assert_eq!(channel::<i32>().0.stats(), None);
+
+        let (tx, _rx) = ChannelBuilder::new().metrics().build();
+        tx.send_all(0..4).unwrap();
+        let stats = tx.stats().unwrap();
+        assert_eq!((stats.sent, stats.depth, stats.peak_depth), (4, 4, 4));
+    }
+
+    #[test]
+    fn latency_test() {
+        let (tx, mut rx) = ChannelBuilder::new().track_latency().build();
+        tx.send(1).unwrap();
+        thread::sleep(Duration::from_millis(20));
+        tx.send(2).unwrap();
+        let (t, latency) = rx.recv_with_latency().unwrap();
+        assert_eq!(t, 1);
+        assert!(latency >= Duration::from_millis(20));
+        assert_eq!(rx.recv(), Some(2));
+        let stats = rx.latency_stats().unwrap();
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.max, latency);
+        assert!(stats.last < latency);
+
+        let (tx, mut rx) = channel();
+        tx.send(1).unwrap();
+        assert_eq!(rx.recv_with_latency(), Some((1, Duration::ZERO)));
+        assert_eq!(rx.latency_stats(), None);
+    }
+
+    #[test]
+    fn handle_count_test() {
+        let (tx, rx) = channel::<i32>();
+        assert_eq!((tx.sender_count(), tx.receiver_count()), (1, 1));
+        let tx2 = tx.clone();
+        let weak = tx.downgrade();
+        let rx2 = rx.clone();
+        assert_eq!((rx.sender_count(), rx.receiver_count()), (2, 2));
+        drop((tx2, rx2));
+        assert_eq!((tx.sender_count(), tx.receiver_count()), (1, 1));
+        drop(tx);
+        assert_eq!(rx.sender_count(), 0);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn debug_test() {
+        struct NotDebug;
+        let (tx, rx) = ChannelBuilder::new().capacity(4).name("jobs").build();
+        tx.send(NotDebug).unwrap();
+        let debug = format!("{rx:?}");
+        assert!(debug.starts_with("Receiver { channel: 0x"));
+        assert!(debug.ends_with(
+            "len: 1, capacity: Some(4), senders: 1, receivers: 1, closed: false, name: \"jobs\" }"
+        ));
+        let guard = tx.shared.inner.lock();
+        assert!(format!("{tx:?}").ends_with(", .. }"));
+        drop(guard);
+    }
+
+    #[test]
+    fn poison_test() {
+        let (tx, mut rx) = ChannelBuilder::new()
+            .ttl(Duration::from_millis(1))
+            .dead_letter(|t: i32| panic!("dead letter {t}"))
+            .build();
+        tx.send(1).unwrap();
+        thread::sleep(Duration::from_millis(5));
+        // The callback panics with the channel lock held, poisoning it.
+        let mut rx2 = rx.clone();
+        assert!(thread::spawn(move || rx2.try_recv()).join().is_err());
+        tx.send(2).unwrap();
+        assert_eq!(rx.recv(), Some(2));
+        assert_eq!(rx.receiver_count(), 1);
+    }
+
+    #[test]
+    fn sender_clone_lock_free_test() {
+        let (tx, mut rx) = channel::<i32>();
+        // Cloning and dropping senders doesn't need the lock.
+        let guard = tx.shared.inner.lock();
+        drop(tx.clone());
+        drop(guard);
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        drop(tx.clone());
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(tx.sender_count(), 1);
+        let handle = thread::spawn(move || rx.recv());
+        drop(tx);
+        assert_eq!(handle.join().unwrap(), None);
+    }
+
+    #[test]
+    fn cache_padded_test() {
+        use std::mem::offset_of;
+
+        let line = std::mem::align_of::<CachePadded<u8>>();
+        assert!(line >= 64);
+        let offsets = [
+            offset_of!(Shared<i32>, inner),
+            offset_of!(Shared<i32>, available),
+            offset_of!(Shared<i32>, space),
+            offset_of!(Shared<i32>, buffered),
+            offset_of!(Shared<i32>, senders),
+        ];
+        for (i, a) in offsets.iter().enumerate() {
+            for b in &offsets[i + 1..] {
+                assert!(a.abs_diff(*b) >= line);
+            }
+        }
+    }
+
+    #[test]
+    fn spin_test() {
+        let (tx, mut rx) = ChannelBuilder::new().spin(1000).build();
+        let handle = thread::spawn(move || {
+            for i in 0..100 {
+                tx.send(i).unwrap();
+            }
+        });
+        assert_eq!(
+            rx.by_ref().collect::<Vec<_>>(),
+            (0..100).collect::<Vec<_>>()
+        );
+        handle.join().unwrap();
+        assert!(format!("{:?}", ChannelBuilder::<i32>::new().spin(8)).contains("spin: 8"));
+    }
+
+    #[test]
+    fn mixed_waiters_test() {
+        let (tx, mut rx) = sync_channel(1);
+        tx.send(1).unwrap();
+        let closer = {
+            let tx = tx.clone();
+            thread::spawn(move || tx.wait_closed())
+        };
+        thread::sleep(Duration::from_millis(20));
+        let sender = {
+            let tx = tx.clone();
+            thread::spawn(move || tx.send(2).unwrap())
+        };
+        thread::sleep(Duration::from_millis(20));
+        // The freed slot must reach the blocked sender, not the thread
+        // waiting for the channel to close.
+        assert_eq!(rx.recv(), Some(1));
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(2));
+        sender.join().unwrap();
+        drop(rx);
+        closer.join().unwrap();
+    }
+
+    #[test]
+    fn multi_receiver_wakeup_test() {
+        let (tx, rx) = channel();
+        let receivers: Vec<_> = (0..4)
+            .map(|_| {
+                let mut rx = rx.clone();
+                thread::spawn(move || rx.recv())
+            })
+            .collect();
+        drop(rx);
+        thread::sleep(Duration::from_millis(20));
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        drop(tx);
+        let mut got: Vec<_> = receivers.into_iter().map(|h| h.join().unwrap()).collect();
+        got.sort();
+        assert_eq!(got, vec![None, None, Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn tee_test() {
+        let (tx, rx) = sync_channel(2);
+        let (mut processor, audit) = rx.tee();
+        assert_eq!(processor.capacity(), Some(2));
+        for i in 0..3 {
+            tx.send(i).unwrap();
+        }
+        assert_eq!(processor.recv(), Some(0));
+        drop(processor);
+        tx.send(3).unwrap();
+        drop(tx);
+        assert_eq!(audit.collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn for_each_parallel_test() {
+        let (tx, rx) = sync_channel(4);
+        let total = AtomicUsize::new(0);
+        let workers = std::sync::Mutex::new(std::collections::HashSet::new());
+        let producer = thread::spawn(move || {
+            for i in 1..=100 {
+                tx.send(i).unwrap();
+            }
+        });
+        rx.for_each_parallel(3, |n| {
+            total.fetch_add(n, Ordering::Relaxed);
+            workers.lock().unwrap().insert(thread::current().id());
+            thread::sleep(Duration::from_micros(100));
+        });
+        producer.join().unwrap();
+        assert_eq!(total.into_inner(), 5050);
+        assert!(!workers.lock().unwrap().contains(&thread::current().id()));
+    }
+
+    #[test]
+    fn fair_senders_test() {
+        let (tx, mut rx) = sync_channel(1);
+        tx.send(0).unwrap();
+        let mut handles = Vec::new();
+        for i in 1..=3 {
+            let sender = tx.clone();
+            handles.push(thread::spawn(move || sender.send(i).unwrap()));
+            // Let each sender block before the next one arrives.
+            while tx.shared.inner.lock().send_queue.len() < i {
+                thread::yield_now();
+            }
+        }
+        assert_eq!(rx.recv(), Some(0));
+        // The freed slot belongs to the longest-waiting sender.
+        assert!(tx.try_send(9).is_err());
+        drop(tx);
+        assert_eq!(rx.by_ref().take(3).collect::<Vec<_>>(), vec![1, 2, 3]);
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn fair_send_all_test() {
+        let (tx, mut rx) = sync_channel(1);
+        tx.send(0).unwrap();
+        let sender = tx.clone();
+        let single = thread::spawn(move || sender.send(1).unwrap());
+        while tx.shared.inner.lock().send_queue.is_empty() {
+            thread::yield_now();
+        }
+        let sender = tx.clone();
+        let batch = thread::spawn(move || sender.send_all(vec![2, 3]).unwrap());
+        while tx.shared.inner.lock().send_queue.len() < 2 {
+            thread::yield_now();
+        }
+        drop(tx);
+        assert_eq!(rx.by_ref().take(4).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+        single.join().unwrap();
+        batch.join().unwrap();
+    }
+
+    #[test]
+    fn pause_test() {
+        let (tx, mut rx) = sync_channel_with_policy(2, OverflowPolicy::DropOldest);
+        rx.pause();
+        assert!(rx.is_paused());
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        let sender = thread::spawn(move || tx.send(3));
+
+        let mut paused = rx.clone();
+        let receiver = thread::spawn(move || paused.recv());
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(rx.len(), 2);
+        rx.resume();
+        let first = receiver.join().unwrap();
+        sender.join().unwrap().unwrap();
+        let mut got: Vec<_> = first.into_iter().chain(rx.try_iter()).collect();
+        got.sort();
+        assert_eq!(got, vec![1, 2, 3]);
+        assert_eq!(rx.dropped(), 0);
+    }
+
+    #[test]
+    fn send_batch_test() {
+        let (tx, mut rx) = sync_channel_with_policy(3, OverflowPolicy::DropOldest);
+        tx.send(0).unwrap();
+        tx.send_batch([1, 2]).unwrap();
+        assert_eq!(
+            tx.send_batch(vec![3, 4]),
+            Err(TrySendError::Full(vec![3, 4]))
+        );
+        assert_eq!(rx.dropped(), 0);
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+        tx.send_batch(3..6).unwrap();
+        assert_eq!(rx.len(), 3);
+        drop(rx);
+        assert_eq!(tx.send_batch([6]), Err(TrySendError::Disconnected(vec![6])));
+    }
+
+    #[test]
+    fn wake_threshold_test() {
+        let (tx, mut rx) = ChannelBuilder::new()
+            .wake_threshold(3, Duration::from_millis(50))
+            .build();
+        let receiver = thread::spawn(move || {
+            let first = rx.recv();
+            (first, rx.try_iter().count(), rx)
+        });
+        thread::sleep(Duration::from_millis(10));
+        for i in 0..3 {
+            tx.send(i).unwrap();
+        }
+        // The receiver only woke up for the full batch.
+        let (first, rest, mut rx) = receiver.join().unwrap();
+        assert_eq!((first, rest), (Some(0), 2));
+
+        // A lone message still arrives once it has waited long enough.
+        let start = Instant::now();
+        tx.send(3).unwrap();
+        assert_eq!(rx.recv(), Some(3));
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(1)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        tx.send(4).unwrap();
+        assert_eq!(rx.recv_timeout(Duration::from_millis(1)), Ok(4));
+        drop(tx);
+        assert_eq!(rx.recv(), None);
+    }
+
+    #[test]
+    fn recv_filter_test() {
+        let (tx, mut rx) = sync_channel(2);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        let sender = thread::spawn(move || {
+            for i in 3..6 {
+                tx.send(i).unwrap();
+            }
+        });
+        // Skipped messages free their slots, so the sender isn't stuck.
+        assert_eq!(rx.recv_filter(|&i| i == 5), Some(5));
+        sender.join().unwrap();
+        assert_eq!(rx.recv_filter(|&i| i % 2 == 0), Some(2));
+        assert_eq!(rx.len(), 3);
+        assert_eq!(rx.recv_filter(|&i| i > 10), None);
+        assert_eq!(rx.collect::<Vec<_>>(), vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn lifo_test() {
+        let (tx, mut rx) = ChannelBuilder::new()
+            .capacity(3)
+            .overflow(OverflowPolicy::DropOldest)
+            .lifo()
+            .build();
+        for i in 0..4 {
+            tx.send(i).unwrap();
+        }
+        assert_eq!(rx.recv(), Some(3));
+        tx.send(4).unwrap();
+        assert_eq!(rx.peek(), Some(&4));
+        assert_eq!(rx.try_recv(), Ok(4));
+        assert_eq!(rx.drain(), [2, 1]);
+    }
+
+    #[test]
+    fn send_front_test() {
+        let (tx, mut rx) = sync_channel(2);
+        tx.send(1).unwrap();
+        tx.send_front(0).unwrap();
+        assert_eq!(tx.send_front(-1), Err(TrySendError::Full(-1)));
+        let t = rx.recv().unwrap();
+        rx.requeue(t);
+        assert_eq!(rx.len(), 2);
+        tx.send(2).unwrap();
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn send_front_batched_test() {
+        let (tx, mut rx) = ChannelBuilder::new()
+            .wake_threshold(2, Duration::from_millis(50))
+            .metrics()
+            .build();
+        let start = Instant::now();
+        tx.send_front(1).unwrap();
+        assert_eq!(rx.recv(), Some(1));
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert_eq!(tx.stats().map(|stats| stats.sent), Some(1));
+    }
+
+    #[test]
+    fn into_remaining_test() {
+        let (tx, mut rx) = channel();
+        for i in 0..4 {
+            tx.send(i).unwrap();
+        }
+        assert_eq!(rx.recv(), Some(0));
+        let other = rx.clone();
+        assert_eq!(rx.into_remaining(), vec![1, 2, 3]);
+        tx.send(4).unwrap();
+        assert_eq!(other.into_remaining(), vec![4]);
+        assert_eq!(tx.send(5), Err(SendError::Disconnected(5)));
+    }
+
+    #[test]
+    fn on_drop_test() {
+        let lost = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&lost);
+        let (tx, mut rx) = ChannelBuilder::new()
+            .on_drop(move |t| sink.lock().push(t))
+            .build();
+        for i in 0..4 {
+            tx.send(i).unwrap();
+        }
+        assert_eq!(rx.recv(), Some(0));
+        let other = rx.clone();
+        drop(other);
+        assert!(lost.lock().is_empty());
+        drop(rx);
+        assert_eq!(*lost.lock(), vec![1, 2, 3]);
+        assert_eq!(tx.send(4), Err(SendError::Disconnected(4)));
+    }
+
+    #[test]
+    fn close_and_drain_test() {
+        let (tx, mut rx) = sync_channel(2);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        let blocked = tx.clone();
+        let sender = thread::spawn(move || blocked.send(3));
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(rx.close_and_drain().collect::<Vec<_>>(), vec![1, 2]);
+        let e = sender.join().unwrap().unwrap_err();
+        assert_eq!(e, SendError::Closed(3));
+        assert_eq!(e.to_string(), "sending on a channel closed to new messages");
+
+        let (tx, mut rx) = channel();
+        tx.send(1).unwrap();
+        assert_eq!(tx.close_and_drain().collect::<Vec<_>>(), vec![1]);
+        assert!(tx.send(2).is_err());
+        assert_eq!(rx.recv(), None);
+    }
+
+    #[test]
+    fn receiver_sender_test() {
+        let (tx, mut rx) = channel();
+        rx.sender().send(1).unwrap();
+        drop(tx);
+        assert_eq!(rx.recv(), Some(1));
+        assert_eq!(rx.recv(), None);
+        let tx = rx.sender();
+        assert_eq!(rx.sender_count(), 1);
+        tx.send(2).unwrap();
+        assert_eq!(rx.recv(), Some(2));
+        drop(tx);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn into_drain_test() {
+        let (tx, rx) = sync_channel(4);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        let mut rx = rx.into_drain();
+        assert_eq!(rx.len(), 2);
+        assert_eq!(rx.next(), Some(1));
+        assert_eq!(rx.next(), Some(2));
+        // The sender is still alive, yet nothing waits.
+        assert_eq!(rx.next(), None);
+        tx.send(3).unwrap();
+        assert_eq!(rx.collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn recv_with_seq_test() {
+        let (tx, mut rx) = ChannelBuilder::new()
+            .capacity(2)
+            .overflow(OverflowPolicy::DropOldest)
+            .sequence_numbers()
+            .build();
+        for c in ['a', 'b', 'c'] {
+            tx.send(c).unwrap();
+        }
+        // 'a' was evicted, which shows as a gap.
+        assert_eq!(rx.recv_with_seq(), Some((2, 'b')));
+        assert_eq!(rx.recv_with_seq(), Some((3, 'c')));
+        tx.send('d').unwrap();
+        assert_eq!(rx.recv_with_seq(), Some((4, 'd')));
+
+        let (tx, mut rx) = channel();
+        tx.send(1).unwrap();
+        assert_eq!(rx.recv_with_seq(), Some((0, 1)));
+    }
+
+    #[test]
+    fn send_all_seq_test() {
+        let (tx, mut rx) = ChannelBuilder::new().sequence_numbers().build();
+        tx.send_all(vec!['a', 'b']).unwrap();
+        assert_eq!(rx.recv_with_seq(), Some((1, 'a')));
+        assert_eq!(rx.recv_with_seq(), Some((2, 'b')));
+    }
+
+    #[test]
+    fn shrink_to_fit_test() {
+        let (tx, mut rx) = ChannelBuilder::new().preallocate(16).build();
+        tx.send_all(0..10_000).unwrap();
+        // The whole queue, allocation and all, moves into the buffer.
+        assert_eq!(rx.try_iter().count(), 10_000);
+        assert!(rx.buffer.capacity() >= 10_000);
+        tx.send_all(0..10_000).unwrap();
+        assert_eq!(rx.try_iter().count(), 10_000);
+        rx.shrink_to_fit();
+        let capacity = tx.shared.inner.lock().queue.capacity();
+        assert!((16..1024).contains(&capacity));
+        assert!((16..1024).contains(&rx.buffer.capacity()));
+    }
+
+    #[test]
+    fn recv_into_test() {
+        let (tx, mut rx) = channel();
+        tx.send_all(0..5).unwrap();
+        let mut buf = Vec::new();
+        assert_eq!(rx.recv_into(&mut buf, 3), 3);
+        assert_eq!(buf, vec![0, 1, 2]);
+
+        buf.clear();
+        let ptr = buf.as_ptr();
+        assert_eq!(rx.try_recv_into(&mut buf, 1), Ok(1));
+        assert_eq!(rx.try_recv_into(&mut buf, 5), Ok(1));
+        assert_eq!(rx.try_recv_into(&mut buf, 5), Err(TryRecvError::Empty));
+        assert_eq!(buf.as_ptr(), ptr);
+        drop(tx);
+        assert_eq!(rx.recv_into(&mut buf, 5), 0);
+        assert_eq!(buf, vec![3, 4]);
+    }
+
+    // A `VecDeque` of zero-sized messages never allocates and just counts
+    // them, so signal channels like `channel::<()>` are already a counter
+    // behind the lock.
+    #[test]
+    fn zero_sized_test() {
+        let (tx, mut rx) = ChannelBuilder::new().preallocate(1024).build();
+        tx.send_all(core::iter::repeat_n((), 100_000)).unwrap();
+        assert_eq!(tx.shared.inner.lock().queue.capacity(), usize::MAX);
+        assert_eq!(rx.len(), 100_000);
+        assert_eq!(rx.try_iter().count(), 100_000);
+        assert_eq!(rx.buffer.capacity(), usize::MAX);
+    }
+
+    #[test]
+    fn drop_clone_keeps_buffer_test() {
+        let (tx, mut rx) = channel();
+        tx.send_all(0..5).unwrap();
+        assert_eq!(rx.recv(), Some(0));
+        let mut other = rx.clone();
+        drop(rx);
+        assert_eq!(other.len(), 4);
+        tx.send(5).unwrap();
+        assert_eq!(other.try_iter().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+    }
+
+    // Nothing in the channel needs `T: 'static`, so handles can carry borrows
+    // into scoped threads as they are.
+    #[test]
+    fn scoped_borrow_test() {
+        let words = vec![String::from("a"), String::from("b")];
+        let (tx, rx) = sync_channel::<&str>(1);
+        let joined = thread::scope(|scope| {
+            scope.spawn(|| {
+                for word in &words {
+                    tx.send(word).unwrap();
+                }
+                drop(tx);
+            });
+            scope.spawn(|| rx.collect::<String>()).join().unwrap()
+        });
+        assert_eq!(joined, "ab");
+    }
+}
+
+// Run with `RUSTFLAGS="--cfg loom" cargo test --release --lib loom`.
+#[cfg(all(test, loom))]
+mod loom_test {
+    use loom::thread;
+
+    use super::*;
+
+    #[test]
+    fn loom_send_recv_test() {
+        loom::model(|| {
+            let (tx, mut rx) = channel();
+            let tx2 = tx.clone();
+            let handle = thread::spawn(move || tx2.send(1).unwrap());
+            tx.send(2).unwrap();
+            let mut got = [rx.recv().unwrap(), rx.recv().unwrap()];
+            got.sort();
+            assert_eq!(got, [1, 2]);
+            handle.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn loom_bounded_test() {
+        loom::model(|| {
+            let (tx, mut rx) = sync_channel(1);
+            let handle = thread::spawn(move || {
+                tx.send(1).unwrap();
+                tx.send(2).unwrap();
+            });
+            assert_eq!(rx.recv(), Some(1));
+            assert_eq!(rx.recv(), Some(2));
+            assert_eq!(rx.recv(), None);
+            handle.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn loom_drop_test() {
+        loom::model(|| {
+            let (tx, mut rx) = channel::<i32>();
+            let handle = thread::spawn(move || drop(tx));
+            assert_eq!(rx.recv(), None);
+            handle.join().unwrap();
+        });
     }
 }
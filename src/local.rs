@@ -0,0 +1,130 @@
+// A channel for handles that never leave one thread, with no locking at all.
+// Nothing else can run while a receiver would be waiting, so receives never
+// block: `recv` returns `None` once the queue is empty.
+
+use alloc::{collections::VecDeque, rc::Rc};
+use core::cell::RefCell;
+
+use crate::{SendError, TryRecvError};
+
+pub struct Sender<T> {
+    shared: Rc<RefCell<Inner<T>>>,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.borrow_mut().senders += 1;
+        Sender {
+            shared: Rc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        let mut inner = self.shared.borrow_mut();
+        if !inner.receiver_alive {
+            return Err(SendError::Disconnected(t));
+        }
+        inner.queue.push_back(t);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.borrow().queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.shared.borrow_mut().senders -= 1;
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Rc<RefCell<Inner<T>>>,
+}
+
+impl<T> Receiver<T> {
+    pub fn recv(&mut self) -> Option<T> {
+        self.shared.borrow_mut().queue.pop_front()
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let mut inner = self.shared.borrow_mut();
+        match inner.queue.pop_front() {
+            Some(t) => Ok(t),
+            None if inner.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.borrow().queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.borrow_mut().receiver_alive = false;
+    }
+}
+
+impl<T> Iterator for Receiver<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+struct Inner<T> {
+    queue: VecDeque<T>,
+    senders: usize,
+    receiver_alive: bool,
+}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Inner {
+        queue: VecDeque::new(),
+        senders: 1,
+        receiver_alive: true,
+    };
+
+    let shared = Rc::new(RefCell::new(inner));
+
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn local_test() {
+        let (tx, mut rx) = channel();
+        let tx2 = tx.clone();
+        tx.send(1).unwrap();
+        tx2.send(2).unwrap();
+        assert_eq!(rx.len(), 2);
+        assert_eq!(rx.by_ref().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        drop((tx, tx2));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+        let (tx, rx) = channel();
+        drop(rx);
+        assert_eq!(tx.send(1), Err(SendError::Disconnected(1)));
+    }
+}
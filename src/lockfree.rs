@@ -0,0 +1,369 @@
+// An unbounded MPSC channel whose data path takes no locks. Messages live in a
+// linked list of fixed-size blocks: senders claim a slot by bumping the tail
+// index, the receiver walks the head forward and frees each block once it
+// has read every slot in it.
+//
+// Indices count in steps of `1 << SHIFT`; the low bit of the tail marks a
+// dropped receiver. Each block spans a lap of `LAP` indices but holds only
+// `BLOCK_CAP` slots, and the spare index tells senders that the next block is
+// still being installed.
+
+use std::{
+    cell::UnsafeCell,
+    hint,
+    mem::MaybeUninit,
+    ptr,
+    sync::{
+        atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::{self, Thread},
+    time::{Duration, Instant},
+};
+
+use crate::{sync::Mutex, RecvTimeoutError, SendError, TryRecvError};
+
+const SHIFT: usize = 1;
+const MARK_BIT: usize = 1;
+const LAP: usize = 32;
+const BLOCK_CAP: usize = LAP - 1;
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::Relaxed);
+        Sender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        let shared = &*self.shared;
+        let mut tail = shared.tail.load(Ordering::Acquire);
+        let mut block = shared.tail_block.load(Ordering::Acquire);
+        let mut next_block = None;
+        loop {
+            if tail & MARK_BIT != 0 {
+                return Err(SendError::Disconnected(t));
+            }
+            let offset = (tail >> SHIFT) % LAP;
+            if offset == BLOCK_CAP {
+                // Another sender is installing the next block.
+                hint::spin_loop();
+                tail = shared.tail.load(Ordering::Acquire);
+                block = shared.tail_block.load(Ordering::Acquire);
+                continue;
+            }
+            // Allocate before claiming the last slot so the window in which
+            // other senders spin stays short.
+            if offset + 1 == BLOCK_CAP && next_block.is_none() {
+                next_block = Some(Block::new());
+            }
+            let new_tail = tail + (1 << SHIFT);
+            match shared.tail.compare_exchange_weak(
+                tail,
+                new_tail,
+                Ordering::SeqCst,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => unsafe {
+                    // SAFETY: the block can't be freed before the slot we
+                    // just claimed in it has been written and read.
+                    if offset + 1 == BLOCK_CAP {
+                        let next_block = Box::into_raw(next_block.unwrap());
+                        shared.tail_block.store(next_block, Ordering::Release);
+                        shared.tail.fetch_add(1 << SHIFT, Ordering::Release);
+                        (*block).next.store(next_block, Ordering::Release);
+                    }
+                    let slot = &(*block).slots[offset];
+                    slot.value.get().write(MaybeUninit::new(t));
+                    slot.ready.store(true, Ordering::Release);
+                    break;
+                },
+                Err(current) => {
+                    tail = current;
+                    block = shared.tail_block.load(Ordering::Acquire);
+                }
+            }
+        }
+        shared.wake();
+        Ok(())
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.shared.wake();
+        }
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Receiver<T> {
+    pub fn recv(&mut self) -> Option<T> {
+        self.recv_until(None).ok()
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        match self.pop() {
+            Some(t) => Ok(t),
+            None if self.shared.senders.load(Ordering::SeqCst) == 0 => {
+                // The last sender may have finished a send just before
+                // dropping.
+                self.pop().ok_or(TryRecvError::Disconnected)
+            }
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Instant::now().checked_add(timeout))
+    }
+
+    pub fn len(&self) -> usize {
+        let shared = &*self.shared;
+        let mut tail = shared.tail.load(Ordering::SeqCst) & !MARK_BIT;
+        let head = shared.head.load(Ordering::Relaxed);
+        // Step over the spare index of a block that is being installed.
+        if (tail >> SHIFT) % LAP == BLOCK_CAP {
+            tail += 1 << SHIFT;
+        }
+        // Rebase both onto the head's lap so the spare indices in between can
+        // be subtracted.
+        let lap = (head >> SHIFT) / LAP * LAP;
+        let tail = (tail >> SHIFT) - lap;
+        let head = (head >> SHIFT) - lap;
+        tail - head - tail / LAP
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn recv_until(&mut self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        loop {
+            match self.try_recv() {
+                Ok(t) => return Ok(t),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => {}
+            }
+            *self.shared.waiter.lock() = Some(thread::current());
+            self.shared.waiting.store(true, Ordering::SeqCst);
+            // Re-check now that senders can see we're about to park.
+            match self.try_recv() {
+                Ok(t) => {
+                    self.shared.waiting.store(false, Ordering::Relaxed);
+                    return Ok(t);
+                }
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => {}
+            }
+            match deadline {
+                None => thread::park(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        self.shared.waiting.store(false, Ordering::Relaxed);
+                        return Err(RecvTimeoutError::Timeout);
+                    }
+                    thread::park_timeout(deadline - now);
+                }
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let shared = &*self.shared;
+        // Only the receiver moves the head, so it can't change under us.
+        let head = shared.head.load(Ordering::Relaxed);
+        let block = shared.head_block.load(Ordering::Relaxed);
+        let tail = shared.tail.load(Ordering::SeqCst);
+        if head >> SHIFT == tail >> SHIFT {
+            return None;
+        }
+        let offset = (head >> SHIFT) % LAP;
+        unsafe {
+            // SAFETY: the slot was claimed by a sender, and the block stays
+            // alive until we move past it below.
+            let slot = &(*block).slots[offset];
+            while !slot.ready.load(Ordering::Acquire) {
+                hint::spin_loop();
+            }
+            let t = slot.value.get().read().assume_init();
+            let mut new_head = head + (1 << SHIFT);
+            if offset + 1 == BLOCK_CAP {
+                // The last slot's sender linked the next block before
+                // marking the slot ready.
+                let next = (*block).next.load(Ordering::Acquire);
+                new_head += 1 << SHIFT;
+                shared.head_block.store(next, Ordering::Relaxed);
+                drop(Box::from_raw(block));
+            }
+            shared.head.store(new_head, Ordering::Relaxed);
+            Some(t)
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.tail.fetch_or(MARK_BIT, Ordering::SeqCst);
+    }
+}
+
+impl<T> Iterator for Receiver<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+struct Shared<T> {
+    head: AtomicUsize,
+    head_block: AtomicPtr<Block<T>>,
+    tail: AtomicUsize,
+    tail_block: AtomicPtr<Block<T>>,
+    senders: AtomicUsize,
+    // Set while the receiver is about to park; senders only touch `waiter`
+    // when it is.
+    waiting: AtomicBool,
+    waiter: Mutex<Option<Thread>>,
+}
+
+// SAFETY: messages are only ever moved from a sender to the receiver.
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    fn wake(&self) {
+        if self.waiting.swap(false, Ordering::SeqCst) {
+            if let Some(thread) = &*self.waiter.lock() {
+                thread.unpark();
+            }
+        }
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let mut head = *self.head.get_mut();
+        let tail = *self.tail.get_mut() & !MARK_BIT;
+        let mut block = *self.head_block.get_mut();
+        // Every handle is gone, so every claimed slot has been written.
+        unsafe {
+            while head != tail {
+                let offset = (head >> SHIFT) % LAP;
+                if offset < BLOCK_CAP {
+                    (*block).slots[offset].value.get_mut().assume_init_drop();
+                } else {
+                    let next = *(*block).next.get_mut();
+                    drop(Box::from_raw(block));
+                    block = next;
+                }
+                head += 1 << SHIFT;
+            }
+            drop(Box::from_raw(block));
+        }
+    }
+}
+
+struct Block<T> {
+    slots: [Slot<T>; BLOCK_CAP],
+    next: AtomicPtr<Block<T>>,
+}
+
+impl<T> Block<T> {
+    fn new() -> Box<Self> {
+        Box::new(Block {
+            slots: std::array::from_fn(|_| Slot {
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+                ready: AtomicBool::new(false),
+            }),
+            next: AtomicPtr::new(ptr::null_mut()),
+        })
+    }
+}
+
+struct Slot<T> {
+    value: UnsafeCell<MaybeUninit<T>>,
+    ready: AtomicBool,
+}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let block = Box::into_raw(Block::new());
+
+    let shared = Shared {
+        head: AtomicUsize::new(0),
+        head_block: AtomicPtr::new(block),
+        tail: AtomicUsize::new(0),
+        tail_block: AtomicPtr::new(block),
+        senders: AtomicUsize::new(1),
+        waiting: AtomicBool::new(false),
+        waiter: Mutex::new(None),
+    };
+
+    let shared = Arc::new(shared);
+
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lockfree_test() {
+        let (tx, mut rx) = channel();
+        for i in 0..100 {
+            tx.send(i).unwrap();
+        }
+        assert_eq!(rx.len(), 100);
+        assert_eq!(rx.try_recv(), Ok(0));
+        assert_eq!(rx.len(), 99);
+        drop(tx);
+        assert_eq!(rx.collect::<Vec<_>>(), (1..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn lockfree_producers_test() {
+        let (tx, mut rx) = channel();
+        let handles: Vec<_> = (0..8)
+            .map(|p| {
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for i in 0..1000 {
+                        tx.send((p, i)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop(tx);
+        let mut next = [0; 8];
+        while let Some((p, i)) = rx.recv() {
+            assert_eq!(next[p], i);
+            next[p] += 1;
+        }
+        assert_eq!(next, [1000; 8]);
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+}
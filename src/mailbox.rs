@@ -0,0 +1,116 @@
+// A worker thread that owns a channel's receiving end and runs a handler for
+// every message. The worker finishes the backlog and exits once the last
+// `Address` is dropped.
+
+use std::{
+    fmt,
+    thread::{self, JoinHandle},
+};
+
+use crate::{channel, sync_channel, Receiver, SendError, Sender, TrySendError};
+
+pub struct Address<T> {
+    sender: Sender<T>,
+}
+
+impl<T> Clone for Address<T> {
+    fn clone(&self) -> Self {
+        Address {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<T> Address<T> {
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        self.sender.send(t)
+    }
+
+    pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        self.sender.try_send(t)
+    }
+
+    // True once the worker has stopped, e.g. because the handler panicked.
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sender.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sender.is_empty()
+    }
+}
+
+impl<T> fmt::Debug for Address<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Address")
+            .field("sender", &self.sender)
+            .finish()
+    }
+}
+
+pub fn spawn<T, F>(handler: F) -> (Address<T>, JoinHandle<()>)
+where
+    T: Send + 'static,
+    F: FnMut(T) + Send + 'static,
+{
+    start(channel(), handler)
+}
+
+// Senders block once `capacity` messages are waiting for the worker.
+pub fn spawn_bounded<T, F>(capacity: usize, handler: F) -> (Address<T>, JoinHandle<()>)
+where
+    T: Send + 'static,
+    F: FnMut(T) + Send + 'static,
+{
+    start(sync_channel(capacity), handler)
+}
+
+fn start<T, F>(
+    (sender, receiver): (Sender<T>, Receiver<T>),
+    handler: F,
+) -> (Address<T>, JoinHandle<()>)
+where
+    T: Send + 'static,
+    F: FnMut(T) + Send + 'static,
+{
+    let handle = thread::Builder::new()
+        .name("channel-mailbox".into())
+        .spawn(move || receiver.for_each(handler))
+        .expect("failed to spawn mailbox thread");
+    (Address { sender }, handle)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[test]
+    fn mailbox_test() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let (address, handle) = {
+            let seen = Arc::clone(&seen);
+            spawn(move |n: i32| seen.lock().unwrap().push(n))
+        };
+        let other = address.clone();
+        address.send(1).unwrap();
+        other.send(2).unwrap();
+        drop((address, other));
+        handle.join().unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn mailbox_panic_test() {
+        let (address, handle) = spawn_bounded(1, |_: ()| panic!("handler failed"));
+        address.send(()).unwrap();
+        assert!(handle.join().is_err());
+        assert!(address.is_closed());
+        assert_eq!(address.send(()), Err(SendError::Disconnected(())));
+    }
+}
@@ -0,0 +1,195 @@
+// Several typed lanes sharing one queue, so a single consumer sees messages
+// from all of them in the order they were sent. The consumer tells lanes
+// apart by `LaneId` and gets the typed value back with `Message::downcast`.
+
+use std::{
+    any::Any,
+    fmt,
+    marker::PhantomData,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{Receiver, SendError, Sender, TrySendError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LaneId(usize);
+
+pub struct Message {
+    lane: LaneId,
+    payload: Box<dyn Any + Send>,
+}
+
+impl Message {
+    pub fn lane(&self) -> LaneId {
+        self.lane
+    }
+
+    pub fn is<T: Any>(&self) -> bool {
+        self.payload.is::<T>()
+    }
+
+    // Hands the message back if it holds some other type.
+    pub fn downcast<T: Any>(self) -> Result<T, Message> {
+        match self.payload.downcast() {
+            Ok(t) => Ok(*t),
+            Err(payload) => Err(Message {
+                lane: self.lane,
+                payload,
+            }),
+        }
+    }
+}
+
+impl fmt::Debug for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Message")
+            .field("lane", &self.lane)
+            .finish_non_exhaustive()
+    }
+}
+
+// Makes lanes. It holds a sender of its own, so the receiver only sees a
+// disconnect once the `Mux` and every lane are gone.
+pub struct Mux {
+    sender: Sender<Message>,
+    next: AtomicUsize,
+}
+
+impl Mux {
+    pub fn lane<T: Send + 'static>(&self) -> Lane<T> {
+        Lane {
+            sender: self.sender.clone(),
+            id: LaneId(self.next.fetch_add(1, Ordering::Relaxed)),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl fmt::Debug for Mux {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mux").field("sender", &self.sender).finish()
+    }
+}
+
+pub struct Lane<T> {
+    sender: Sender<Message>,
+    id: LaneId,
+    // Lanes are `Send + Sync` whatever `T` is, as they only move `T` along.
+    _marker: PhantomData<fn(T)>,
+}
+
+impl<T> Clone for Lane<T> {
+    fn clone(&self) -> Self {
+        Lane {
+            sender: self.sender.clone(),
+            id: self.id,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Send + 'static> Lane<T> {
+    pub fn id(&self) -> LaneId {
+        self.id
+    }
+
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        self.sender.send(self.wrap(t)).map_err(|e| match e {
+            SendError::Full(m) => SendError::Full(unwrap(m)),
+            SendError::Disconnected(m) => SendError::Disconnected(unwrap(m)),
+            SendError::Closed(m) => SendError::Closed(unwrap(m)),
+        })
+    }
+
+    pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        self.sender.try_send(self.wrap(t)).map_err(|e| match e {
+            TrySendError::Full(m) => TrySendError::Full(unwrap(m)),
+            TrySendError::Disconnected(m) => TrySendError::Disconnected(unwrap(m)),
+            TrySendError::Closed(m) => TrySendError::Closed(unwrap(m)),
+        })
+    }
+
+    fn wrap(&self, t: T) -> Message {
+        Message {
+            lane: self.id,
+            payload: Box::new(t),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Lane<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lane").field("id", &self.id).finish()
+    }
+}
+
+// Only called on messages a `Lane<T>` just wrapped.
+fn unwrap<T: Any>(m: Message) -> T {
+    match m.downcast() {
+        Ok(t) => t,
+        Err(_) => unreachable!("lane message changed type"),
+    }
+}
+
+pub fn channel() -> (Mux, Receiver<Message>) {
+    let (sender, receiver) = crate::channel();
+    (
+        Mux {
+            sender,
+            next: AtomicUsize::new(0),
+        },
+        receiver,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mux_test() {
+        let (mux, rx) = channel();
+        let numbers = mux.lane::<u32>();
+        let words = mux.lane::<&str>();
+        let more = mux.lane::<u32>();
+        let ids = [numbers.id(), words.id(), more.id()];
+        drop(mux);
+        numbers.send(1).unwrap();
+        words.send("two").unwrap();
+        more.send(3).unwrap();
+        numbers.clone().send(4).unwrap();
+        drop((numbers, words, more));
+
+        let mut seen = Vec::new();
+        for message in rx {
+            let lane = ids.iter().position(|id| *id == message.lane()).unwrap();
+            let text = match message.downcast::<u32>() {
+                Ok(n) => n.to_string(),
+                Err(message) => message.downcast::<&str>().unwrap().to_string(),
+            };
+            seen.push(format!("{}:{}", lane, text));
+        }
+        assert_eq!(seen, ["0:1", "1:two", "2:3", "0:4"]);
+    }
+
+    #[test]
+    fn mux_disconnected_test() {
+        let (mux, rx) = channel();
+        let lane = mux.lane::<String>();
+        drop(rx);
+        assert_eq!(
+            lane.send("lost".into()),
+            Err(SendError::Disconnected("lost".into()))
+        );
+        assert_eq!(
+            lane.try_send("a".into()),
+            Err(TrySendError::Disconnected("a".into()))
+        );
+        let message = Message {
+            lane: lane.id(),
+            payload: Box::new(1u8),
+        };
+        assert!(message.is::<u8>());
+        assert!(message.downcast::<String>().is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    sync::{Arc, Condvar, Mutex},
+    RecvTimeoutError, SendError, TryRecvError,
+};
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    pub fn send(self, t: T) -> Result<(), SendError<T>> {
+        let mut inner = self.shared.inner.lock();
+        if !inner.receiver_alive {
+            return Err(SendError::Disconnected(t));
+        }
+        inner.value = Some(t);
+        drop(inner);
+        self.shared.available.notify_one();
+        Ok(())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        !self.shared.inner.lock().receiver_alive
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut inner = self.shared.inner.lock();
+        inner.sender_alive = false;
+        drop(inner);
+        self.shared.available.notify_one();
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Receiver<T> {
+    pub fn recv(self) -> Option<T> {
+        let mut inner = self.shared.inner.lock();
+        loop {
+            if let Some(t) = inner.value.take() {
+                return Some(t);
+            }
+            if !inner.sender_alive {
+                return None;
+            }
+            inner = self.shared.available.wait(inner);
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let mut inner = self.shared.inner.lock();
+        match inner.value.take() {
+            Some(t) => Ok(t),
+            None if !inner.sender_alive => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut inner = self.shared.inner.lock();
+        loop {
+            if let Some(t) = inner.value.take() {
+                return Ok(t);
+            }
+            if !inner.sender_alive {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            let (guard, timed_out) = crate::wait_until(&self.shared.available, inner, deadline);
+            inner = guard;
+            if timed_out {
+                return Err(RecvTimeoutError::Timeout);
+            }
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut inner = self.shared.inner.lock();
+        inner.receiver_alive = false;
+    }
+}
+
+struct Shared<T> {
+    inner: Mutex<Inner<T>>,
+    available: Condvar,
+}
+
+struct Inner<T> {
+    value: Option<T>,
+    sender_alive: bool,
+    receiver_alive: bool,
+}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Inner {
+        value: None,
+        sender_alive: true,
+        receiver_alive: true,
+    };
+
+    let shared = Shared {
+        inner: Mutex::new(inner),
+        available: Condvar::new(),
+    };
+
+    let shared = Arc::new(shared);
+
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn oneshot_test() {
+        let (tx, rx) = channel::<i32>();
+        let handle = thread::spawn(move || rx.recv());
+        tx.send(42).unwrap();
+        assert_eq!(handle.join().unwrap(), Some(42));
+    }
+
+    #[test]
+    fn oneshot_drop_test() {
+        let (tx, mut rx) = channel::<i32>();
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        drop(tx);
+        assert_eq!(rx.recv(), None);
+
+        let (tx, rx) = channel::<i32>();
+        drop(rx);
+        assert!(tx.is_closed());
+        assert_eq!(tx.send(1), Err(SendError::Disconnected(1)));
+    }
+}
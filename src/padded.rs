@@ -0,0 +1,31 @@
+use core::ops::{Deref, DerefMut};
+
+// Keeps `T` on its own cache line so that writes to it don't invalidate
+// neighbouring fields on other cores. Modern x86_64 and aarch64 parts
+// prefetch cache lines in pairs, hence 128 bytes there.
+#[cfg_attr(any(target_arch = "x86_64", target_arch = "aarch64"), repr(align(128)))]
+#[cfg_attr(
+    not(any(target_arch = "x86_64", target_arch = "aarch64")),
+    repr(align(64))
+)]
+#[derive(Default)]
+pub(crate) struct CachePadded<T>(T);
+
+impl<T> CachePadded<T> {
+    pub(crate) const fn new(t: T) -> Self {
+        CachePadded(t)
+    }
+}
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for CachePadded<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
@@ -0,0 +1,265 @@
+// A slot claimed ahead of the message that fills it. Permits count against a
+// bounded channel's capacity, though not its byte budget, until they're used
+// or dropped.
+
+use core::{error::Error, fmt};
+
+use crate::{Refusal, SendError, Sender, Shared, CLOSED_TO_SENDS};
+
+impl<T> Sender<T> {
+    // Blocks until there's room, whatever the overflow policy. A rendezvous
+    // channel has no slots, so reserving on one fails with `Full`.
+    pub fn reserve(&self) -> Result<Permit<'_, T>, SendError<()>> {
+        self.shared.claim()?;
+        Ok(Permit { sender: self })
+    }
+
+    // Claims a slot only if one is free right now.
+    pub fn try_reserve(&self) -> Result<Permit<'_, T>, TryReserveError> {
+        self.shared.try_claim()?;
+        Ok(Permit { sender: self })
+    }
+
+    // Like `reserve`, but the permit owns the sender so it can be moved to
+    // another thread. The sender is handed back once the permit is used.
+    pub fn reserve_owned(self) -> Result<OwnedPermit<T>, SendError<Sender<T>>> {
+        match self.shared.claim() {
+            Ok(()) => Ok(OwnedPermit { sender: Some(self) }),
+            Err(e) => Err(match e {
+                SendError::Full(()) => SendError::Full(self),
+                SendError::Disconnected(()) => SendError::Disconnected(self),
+                SendError::Closed(()) => SendError::Closed(self),
+            }),
+        }
+    }
+}
+
+impl<T> Shared<T> {
+    fn claim(&self) -> Result<(), SendError<()>> {
+        let mut inner = self.inner.lock();
+        let mut ticket = None;
+        loop {
+            if let Some(refusal) = inner.refusal() {
+                self.leave_queue(&mut inner, ticket);
+                return Err(refusal.send(()));
+            }
+            // A rendezvous channel never has a slot to hand out.
+            if inner.capacity == Some(0) {
+                return Err(SendError::Full(()));
+            }
+            if inner.is_next(ticket) && !inner.is_full() {
+                break;
+            }
+            ticket = ticket.or_else(|| Some(inner.join_queue()));
+            inner = self.space.wait(inner);
+        }
+        self.leave_queue(&mut inner, ticket);
+        inner.reserved += 1;
+        Ok(())
+    }
+
+    fn try_claim(&self) -> Result<(), TryReserveError> {
+        let mut inner = self.inner.lock();
+        match inner.refusal() {
+            Some(Refusal::Disconnected) => Err(TryReserveError::Disconnected),
+            Some(Refusal::Closed) => Err(TryReserveError::Closed),
+            None if inner.capacity == Some(0) => Err(TryReserveError::Full),
+            None if inner.is_full() || !inner.is_next(None) => Err(TryReserveError::Full),
+            None => {
+                inner.reserved += 1;
+                Ok(())
+            }
+        }
+    }
+
+    // If the receiving side went away in the meantime the message is dropped,
+    // as it would have been had it been queued.
+    fn fill(&self, t: T) {
+        let mut inner = self.inner.lock();
+        inner.reserved -= 1;
+        if inner.refusal().is_some() {
+            if let Some(on_drop) = &mut inner.on_drop {
+                on_drop(t);
+            }
+            return;
+        }
+        self.push(&mut inner, t);
+        if inner.defers_wake() {
+            return;
+        }
+        inner.wake_receivers();
+        drop(inner);
+        self.available.notify_one();
+    }
+
+    fn release(&self) {
+        let mut inner = self.inner.lock();
+        inner.reserved -= 1;
+        inner.wake_senders();
+        self.notify_space(&inner);
+    }
+}
+
+pub struct Permit<'a, T> {
+    sender: &'a Sender<T>,
+}
+
+impl<T> Permit<'_, T> {
+    // Never blocks or fails.
+    pub fn send(self, t: T) {
+        let sender = self.sender;
+        core::mem::forget(self);
+        sender.shared.fill(t);
+    }
+}
+
+impl<T> Drop for Permit<'_, T> {
+    fn drop(&mut self) {
+        self.sender.shared.release();
+    }
+}
+
+impl<T> fmt::Debug for Permit<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Permit").finish_non_exhaustive()
+    }
+}
+
+pub struct OwnedPermit<T> {
+    // Only empty once the permit has been used.
+    sender: Option<Sender<T>>,
+}
+
+impl<T> OwnedPermit<T> {
+    // Never blocks or fails.
+    pub fn send(mut self, t: T) -> Sender<T> {
+        let sender = self.sender.take().expect("permit already used");
+        sender.shared.fill(t);
+        sender
+    }
+
+    // Gives the slot back without sending anything.
+    pub fn release(mut self) -> Sender<T> {
+        let sender = self.sender.take().expect("permit already used");
+        sender.shared.release();
+        sender
+    }
+}
+
+impl<T> Drop for OwnedPermit<T> {
+    fn drop(&mut self) {
+        if let Some(sender) = &self.sender {
+            sender.shared.release();
+        }
+    }
+}
+
+impl<T> fmt::Debug for OwnedPermit<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OwnedPermit").finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryReserveError {
+    Full,
+    Disconnected,
+    Closed,
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryReserveError::Full => "reserving on a full channel".fmt(f),
+            TryReserveError::Disconnected => "reserving on a closed channel".fmt(f),
+            TryReserveError::Closed => CLOSED_TO_SENDS.fmt(f),
+        }
+    }
+}
+
+impl Error for TryReserveError {}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use std::{thread, time::Duration};
+
+    use super::*;
+    use crate::sync_channel;
+
+    #[test]
+    fn reserve_test() {
+        let (tx, mut rx) = sync_channel::<i32>(2);
+        let first = tx.reserve().unwrap();
+        tx.send(1).unwrap();
+        assert_eq!(tx.remaining(), Some(0));
+        assert!(tx.try_send(2).is_err());
+        first.send(0);
+        assert_eq!(rx.recv(), Some(1));
+        assert_eq!(rx.recv(), Some(0));
+
+        let unused = tx.reserve().unwrap();
+        let held = tx.reserve().unwrap();
+        drop(unused);
+        assert_eq!(tx.remaining(), Some(1));
+        drop(held);
+        assert_eq!(tx.remaining(), Some(2));
+    }
+
+    #[test]
+    fn reserve_blocks_test() {
+        let (tx, mut rx) = sync_channel::<i32>(1);
+        tx.send(1).unwrap();
+        let handle = thread::spawn(move || {
+            let permit = tx.reserve().unwrap();
+            permit.send(2);
+            tx
+        });
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(rx.recv(), Some(1));
+        assert_eq!(rx.recv(), Some(2));
+        let tx = handle.join().unwrap();
+        drop(rx);
+        assert_eq!(tx.reserve().unwrap_err(), SendError::Disconnected(()));
+    }
+
+    #[test]
+    fn try_reserve_test() {
+        let (tx, rx) = sync_channel::<i32>(1);
+        let permit = tx.try_reserve().unwrap();
+        assert_eq!(tx.try_reserve().unwrap_err(), TryReserveError::Full);
+        drop(permit);
+        tx.try_reserve().unwrap().send(1);
+        assert_eq!(tx.try_reserve().unwrap_err(), TryReserveError::Full);
+        drop(rx);
+        let e = tx.try_reserve().unwrap_err();
+        assert_eq!(e, TryReserveError::Disconnected);
+        assert_eq!(e.to_string(), "reserving on a closed channel");
+    }
+
+    #[test]
+    fn reserve_rendezvous_test() {
+        let (tx, _rx) = sync_channel::<i32>(0);
+        assert_eq!(tx.reserve().unwrap_err(), SendError::Full(()));
+        assert_eq!(tx.try_reserve().unwrap_err(), TryReserveError::Full);
+        assert!(matches!(tx.reserve_owned(), Err(SendError::Full(_))));
+    }
+
+    #[test]
+    fn reserve_owned_test() {
+        let (tx, mut rx) = sync_channel::<String>(1);
+        let permit = tx.clone().reserve_owned().unwrap();
+        assert!(tx.try_send("late".into()).is_err());
+        let worker = thread::spawn(move || permit.send("filled".into()));
+        let sender = worker.join().unwrap();
+        assert!(sender.same_channel(&tx));
+        assert_eq!(rx.recv().as_deref(), Some("filled"));
+
+        let sender = tx.reserve_owned().unwrap().release();
+        assert_eq!(sender.remaining(), Some(1));
+        drop(rx);
+        assert!(matches!(
+            sender.reserve_owned(),
+            Err(SendError::Disconnected(_))
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    collections::BinaryHeap,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    store::{self, Store},
+    RecvTimeoutError, SendError, TryRecvError,
+};
+
+pub struct Sender<T> {
+    inner: store::Sender<BinaryHeap<T>>,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Sender {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: Ord> Sender<T> {
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        self.inner.send(t, BinaryHeap::push)
+    }
+}
+
+pub struct Receiver<T> {
+    inner: store::Receiver<BinaryHeap<T>>,
+}
+
+impl<T: Ord> Receiver<T> {
+    pub fn recv(&mut self) -> Option<T> {
+        self.inner.recv()
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.inner.try_recv()
+    }
+
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.inner.recv_timeout(timeout)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: Ord> Iterator for Receiver<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+impl<T: Ord> Store for BinaryHeap<T> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        BinaryHeap::len(self)
+    }
+
+    fn pop(&mut self) -> Result<T, Option<Instant>> {
+        BinaryHeap::pop(self).ok_or(None)
+    }
+}
+
+pub fn channel<T: Ord>() -> (Sender<T>, Receiver<T>) {
+    let (tx, rx) = store::channel(BinaryHeap::new());
+    (Sender { inner: tx }, Receiver { inner: rx })
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cmp::Reverse, thread};
+
+    use super::*;
+
+    #[test]
+    fn highest_first_test() {
+        let (tx, rx) = channel::<i32>();
+        for i in [3, 1, 4, 1, 5, 9, 2, 6] {
+            tx.send(i).unwrap();
+        }
+        drop(tx);
+        assert_eq!(rx.collect::<Vec<_>>(), vec![9, 6, 5, 4, 3, 2, 1, 1]);
+    }
+
+    #[test]
+    fn blocking_recv_test() {
+        let (tx, mut rx) = channel::<Reverse<u8>>();
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            tx.send(Reverse(2)).unwrap();
+        });
+        assert_eq!(rx.recv(), Some(Reverse(2)));
+        handle.join().unwrap();
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+}
@@ -0,0 +1,127 @@
+// Topic-based publish/subscribe. Every subscriber gets its own bounded
+// channel; a full one loses its oldest message rather than holding up the
+// publisher, and `Receiver::dropped` reports how many it lost.
+
+use std::{borrow::Borrow, collections::HashMap, fmt, hash::Hash};
+
+use crate::{
+    sync::{Arc, Mutex},
+    sync_channel_with_policy, OverflowPolicy, Receiver, Sender, TrySendError,
+};
+
+pub struct Bus<T, K = String> {
+    topics: Arc<Mutex<HashMap<K, Vec<Sender<T>>>>>,
+}
+
+impl<T, K> Clone for Bus<T, K> {
+    fn clone(&self) -> Self {
+        Bus {
+            topics: Arc::clone(&self.topics),
+        }
+    }
+}
+
+impl<T, K> Default for Bus<T, K> {
+    fn default() -> Self {
+        Bus {
+            topics: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<T: Clone, K: Hash + Eq> Bus<T, K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A subscriber always holds at least the latest message: a capacity of
+    // 0 is taken as 1, since a rendezvous channel has nothing to drop.
+    pub fn subscribe(&self, topic: impl Into<K>, capacity: usize) -> Receiver<T> {
+        let (tx, rx) = sync_channel_with_policy(capacity.max(1), OverflowPolicy::DropOldest);
+        self.topics.lock().entry(topic.into()).or_default().push(tx);
+        rx
+    }
+
+    // Returns how many subscribers the message reached. Subscribers that have
+    // gone away are forgotten.
+    pub fn publish<Q>(&self, topic: &Q, t: T) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut topics = self.topics.lock();
+        let Some(subscribers) = topics.get_mut(topic) else {
+            return 0;
+        };
+        subscribers.retain(|tx| match tx.try_send(t.clone()) {
+            Ok(()) | Err(TrySendError::Full(_)) => true,
+            Err(TrySendError::Disconnected(_) | TrySendError::Closed(_)) => false,
+        });
+        let reached = subscribers.len();
+        if reached == 0 {
+            topics.remove(topic);
+        }
+        reached
+    }
+
+    pub fn subscribers<Q>(&self, topic: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.topics.lock().get(topic).map_or(0, |subscribers| {
+            subscribers.iter().filter(|tx| !tx.is_closed()).count()
+        })
+    }
+}
+
+impl<T, K> fmt::Debug for Bus<T, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bus")
+            .field("topics", &self.topics.lock().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bus_test() {
+        let bus = Bus::<u32>::new();
+        let mut prices = bus.subscribe("prices", 4);
+        let mut everything = bus.subscribe("prices", 4);
+        let mut news = bus.clone().subscribe("news", 4);
+        assert_eq!(bus.publish("prices", 10), 2);
+        assert_eq!(bus.publish("news", 1), 1);
+        assert_eq!(bus.publish("weather", 0), 0);
+        assert_eq!(prices.try_recv(), Ok(10));
+        assert_eq!(everything.try_recv(), Ok(10));
+        assert_eq!(news.try_recv(), Ok(1));
+        assert!(prices.is_empty());
+
+        drop(everything);
+        assert_eq!(bus.subscribers("prices"), 1);
+        assert_eq!(bus.publish("prices", 11), 1);
+        drop(news);
+        assert_eq!(bus.publish("news", 2), 0);
+        assert_eq!(format!("{:?}", bus), "Bus { topics: 1 }");
+    }
+
+    #[test]
+    fn bus_slow_subscriber_test() {
+        let bus = Bus::<u32, u8>::new();
+        let mut slow = bus.subscribe(7, 2);
+        for i in 0..5 {
+            assert_eq!(bus.publish(&7, i), 1);
+        }
+        assert_eq!(slow.dropped(), 3);
+        assert_eq!(slow.by_ref().take(2).collect::<Vec<_>>(), vec![3, 4]);
+
+        let mut latest = bus.subscribe(8, 0);
+        assert_eq!(bus.publish(&8, 1), 1);
+        assert_eq!(bus.publish(&8, 2), 1);
+        assert_eq!(latest.try_recv(), Ok(2));
+    }
+}
@@ -0,0 +1,126 @@
+// A sender throttled by a token bucket: up to `burst` messages go through at
+// once, then `rate` per second. Clones share the bucket, so the limit holds
+// across every producer using them.
+
+use std::{
+    fmt, thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    sync::{Arc, Mutex},
+    SendError, Sender, TrySendError,
+};
+
+impl<T> Sender<T> {
+    pub fn rate_limited(self, rate: u32, burst: u32) -> RateLimited<T> {
+        assert!(rate > 0, "rate limit must be non-zero");
+        assert!(burst > 0, "burst must be non-zero");
+        RateLimited {
+            sender: self,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: burst as f64,
+                refilled: Instant::now(),
+            })),
+            rate: rate as f64,
+            burst: burst as f64,
+        }
+    }
+}
+
+pub struct RateLimited<T> {
+    sender: Sender<T>,
+    bucket: Arc<Mutex<Bucket>>,
+    rate: f64,
+    burst: f64,
+}
+
+struct Bucket {
+    // Goes negative while blocked senders are waiting for tokens they've
+    // already claimed.
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl<T> Clone for RateLimited<T> {
+    fn clone(&self) -> Self {
+        RateLimited {
+            sender: self.sender.clone(),
+            bucket: Arc::clone(&self.bucket),
+            rate: self.rate,
+            burst: self.burst,
+        }
+    }
+}
+
+impl<T> RateLimited<T> {
+    // Waits for a token, then for room in the channel.
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        let wait = {
+            let mut bucket = self.refill();
+            bucket.tokens -= 1.0;
+            Duration::from_secs_f64((-bucket.tokens).max(0.0) / self.rate)
+        };
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+        self.sender.send(t)
+    }
+
+    // An empty bucket reports `Full`, like a full channel.
+    pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        let mut bucket = self.refill();
+        if bucket.tokens < 1.0 {
+            return Err(TrySendError::Full(t));
+        }
+        // Only spend the token if the message actually goes out.
+        self.sender.try_send(t)?;
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> Sender<T> {
+        self.sender
+    }
+
+    fn refill(&self) -> crate::sync::MutexGuard<'_, Bucket> {
+        let mut bucket = self.bucket.lock();
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.refilled = now;
+        bucket
+    }
+}
+
+impl<T> fmt::Debug for RateLimited<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimited")
+            .field("sender", &self.sender)
+            .field("rate", &self.rate)
+            .field("burst", &self.burst)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::channel;
+
+    #[test]
+    fn rate_limited_test() {
+        let (tx, rx) = channel();
+        let tx = tx.rate_limited(100, 2);
+        tx.try_send(1).unwrap();
+        tx.clone().try_send(2).unwrap();
+        assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+        let start = Instant::now();
+        tx.send(3).unwrap();
+        tx.send(4).unwrap();
+        // Two tokens at 100 per second take about 20ms to come back.
+        assert!(start.elapsed() >= Duration::from_millis(15));
+        drop(tx);
+        assert_eq!(rx.collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
+}
@@ -0,0 +1,148 @@
+// Fans one upstream receiver out to several downstream senders, picking the
+// destination of each message with a key function.
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    thread::{self, JoinHandle},
+};
+
+use crate::{Receiver, SendError, Sender};
+
+// What to do with a message whose route is missing or has disconnected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnDisconnect<K> {
+    #[default]
+    Drop,
+    Reroute(K),
+}
+
+pub struct Router<K, T> {
+    upstream: Receiver<T>,
+    routes: HashMap<K, Sender<T>>,
+    key: Box<dyn Fn(&T) -> K + Send>,
+    policy: OnDisconnect<K>,
+}
+
+impl<K: Hash + Eq, T> Router<K, T> {
+    pub fn new<F: Fn(&T) -> K + Send + 'static>(upstream: Receiver<T>, key: F) -> Self {
+        Router {
+            upstream,
+            routes: HashMap::new(),
+            key: Box::new(key),
+            policy: OnDisconnect::Drop,
+        }
+    }
+
+    pub fn route(mut self, key: K, sender: Sender<T>) -> Self {
+        self.routes.insert(key, sender);
+        self
+    }
+
+    pub fn on_disconnect(mut self, policy: OnDisconnect<K>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    // Returns once the upstream disconnects or no route is left.
+    pub fn run(mut self) {
+        while !self.routes.is_empty() {
+            match self.upstream.recv() {
+                Some(t) => self.dispatch(t),
+                None => return,
+            }
+        }
+    }
+
+    pub fn spawn(self) -> JoinHandle<()>
+    where
+        K: Send + 'static,
+        T: Send + 'static,
+    {
+        thread::Builder::new()
+            .name("channel-router".into())
+            .spawn(move || self.run())
+            .expect("failed to spawn router thread")
+    }
+
+    // Whatever can't be delivered, even after rerouting, is dropped.
+    fn dispatch(&mut self, t: T) {
+        let key = (self.key)(&t);
+        let Err(t) = deliver(&mut self.routes, &key, t) else {
+            return;
+        };
+        if let OnDisconnect::Reroute(fallback) = &self.policy {
+            if *fallback != key {
+                let _ = deliver(&mut self.routes, fallback, t);
+            }
+        }
+    }
+}
+
+// Hands the message back if its route is missing or gone, dropping the route
+// in the latter case.
+fn deliver<K: Hash + Eq, T>(routes: &mut HashMap<K, Sender<T>>, key: &K, t: T) -> Result<(), T> {
+    let Some(sender) = routes.get(key) else {
+        return Err(t);
+    };
+    match sender.send(t) {
+        Ok(()) => Ok(()),
+        // A rejecting downstream keeps its route; the message is lost.
+        Err(SendError::Full(_)) => Ok(()),
+        Err(SendError::Disconnected(t) | SendError::Closed(t)) => {
+            routes.remove(key);
+            Err(t)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::channel;
+
+    #[test]
+    fn router_test() {
+        let (tx, rx) = channel::<u32>();
+        let (even_tx, even_rx) = channel();
+        let (odd_tx, odd_rx) = channel();
+        let handle = Router::new(rx, |n: &u32| n % 2)
+            .route(0, even_tx)
+            .route(1, odd_tx)
+            .spawn();
+        for i in 0..6 {
+            tx.send(i).unwrap();
+        }
+        drop(tx);
+        handle.join().unwrap();
+        assert_eq!(even_rx.collect::<Vec<_>>(), vec![0, 2, 4]);
+        assert_eq!(odd_rx.collect::<Vec<_>>(), vec![1, 3, 5]);
+    }
+
+    #[test]
+    fn router_reroute_test() {
+        let (tx, rx) = channel::<&str>();
+        let (fast_tx, fast_rx) = channel();
+        let (slow_tx, slow_rx) = channel();
+        drop(fast_rx);
+        tx.send("fast").unwrap();
+        tx.send("slow").unwrap();
+        tx.send("unknown").unwrap();
+        drop(tx);
+        Router::new(rx, |s: &&str| *s)
+            .route("fast", fast_tx)
+            .route("slow", slow_tx)
+            .on_disconnect(OnDisconnect::Reroute("slow"))
+            .run();
+        assert_eq!(slow_rx.collect::<Vec<_>>(), vec!["fast", "slow", "unknown"]);
+
+        let (tx, rx) = channel::<u8>();
+        let (down_tx, down_rx) = channel();
+        drop(down_rx);
+        let handle = Router::new(rx, |_| ()).route((), down_tx).spawn();
+        tx.send(1).unwrap();
+        handle.join().unwrap();
+        // With every route gone the router stops and drops the upstream.
+        assert_eq!(tx.send(2), Err(SendError::Disconnected(2)));
+    }
+}
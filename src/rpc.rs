@@ -0,0 +1,114 @@
+// Request-response over a regular channel: each request carries a oneshot
+// sender for its reply.
+
+use std::{error::Error, fmt};
+
+use crate::{oneshot, SendError, Sender};
+
+pub struct Request<Req, Resp> {
+    body: Req,
+    reply: oneshot::Sender<Resp>,
+}
+
+impl<Req, Resp> Request<Req, Resp> {
+    pub fn body(&self) -> &Req {
+        &self.body
+    }
+
+    // Hands the response back if the caller has stopped waiting.
+    pub fn reply(self, resp: Resp) -> Result<(), Resp> {
+        self.reply.send(resp).map_err(SendError::into_inner)
+    }
+
+    pub fn into_parts(self) -> (Req, oneshot::Sender<Resp>) {
+        (self.body, self.reply)
+    }
+}
+
+impl<Req, Resp> Sender<Request<Req, Resp>> {
+    pub fn call(&self, req: Req) -> Result<Resp, CallError<Req>> {
+        let (reply, response) = oneshot::channel();
+        self.send(Request { body: req, reply })
+            .map_err(|e| CallError::Send(map_send_error(e)))?;
+        response.recv().ok_or(CallError::NoReply)
+    }
+}
+
+fn map_send_error<Req, Resp>(e: SendError<Request<Req, Resp>>) -> SendError<Req> {
+    match e {
+        SendError::Full(r) => SendError::Full(r.body),
+        SendError::Disconnected(r) => SendError::Disconnected(r.body),
+        SendError::Closed(r) => SendError::Closed(r.body),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CallError<T> {
+    // The request never reached the handler and is handed back.
+    Send(SendError<T>),
+    // The handler dropped the request without replying.
+    NoReply,
+}
+
+impl<T> fmt::Debug for CallError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::Send(e) => f.debug_tuple("Send").field(e).finish(),
+            CallError::NoReply => "NoReply".fmt(f),
+        }
+    }
+}
+
+impl<T> fmt::Display for CallError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::Send(e) => e.fmt(f),
+            CallError::NoReply => "request dropped without a reply".fmt(f),
+        }
+    }
+}
+
+impl<T> Error for CallError<T> {}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+    use crate::channel;
+
+    #[test]
+    fn call_test() {
+        let (tx, rx) = channel::<Request<u32, u32>>();
+        let server = thread::spawn(move || {
+            for req in rx {
+                if *req.body() == 0 {
+                    continue;
+                }
+                let double = req.body() * 2;
+                req.reply(double).unwrap();
+            }
+        });
+        assert_eq!(tx.call(21), Ok(42));
+        assert_eq!(tx.call(0), Err(CallError::NoReply));
+        let tx2 = tx.clone();
+        let client = thread::spawn(move || tx2.call(5));
+        assert_eq!(client.join().unwrap(), Ok(10));
+        drop(tx);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn call_disconnected_test() {
+        let (tx, rx) = channel::<Request<&str, ()>>();
+        drop(rx);
+        assert_eq!(
+            tx.call("ping"),
+            Err(CallError::Send(SendError::Disconnected("ping")))
+        );
+        assert_eq!(
+            CallError::<()>::NoReply.to_string(),
+            "request dropped without a reply"
+        );
+    }
+}
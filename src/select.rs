@@ -0,0 +1,297 @@
+use std::{
+    thread::{self, Thread, ThreadId},
+    time::{Duration, Instant},
+};
+
+use crate::{Receiver, RecvTimeoutError, TryRecvError};
+
+pub(crate) trait SelectHandle {
+    // Ready means the next `try_recv` on the handle will not return `Empty`.
+    fn poll_ready(&mut self) -> bool;
+    fn watch(&self, thread: &Thread);
+    fn unwatch(&self, id: ThreadId);
+}
+
+pub struct Select<'a> {
+    handles: Vec<&'a mut dyn SelectHandle>,
+    start: usize,
+}
+
+impl Default for Select<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Select<'a> {
+    pub fn new() -> Self {
+        Select {
+            handles: Vec::new(),
+            start: 0,
+        }
+    }
+
+    pub fn recv<T>(&mut self, rx: &'a mut Receiver<T>) -> usize {
+        self.handles.push(rx);
+        self.handles.len() - 1
+    }
+
+    pub fn try_ready(&mut self) -> Option<usize> {
+        let n = self.handles.len();
+        // Rotate the starting point so a busy receiver can't starve the rest.
+        let start = self.start;
+        self.start = self.start.wrapping_add(1);
+        (0..n)
+            .map(|i| (start + i) % n)
+            .find(|&i| self.handles[i].poll_ready())
+    }
+
+    pub fn ready(&mut self) -> usize {
+        loop {
+            if let Some(i) = self.ready_until(None) {
+                return i;
+            }
+        }
+    }
+
+    pub fn ready_timeout(&mut self, timeout: Duration) -> Option<usize> {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.ready_deadline(deadline),
+            None => Some(self.ready()),
+        }
+    }
+
+    pub fn ready_deadline(&mut self, deadline: Instant) -> Option<usize> {
+        loop {
+            if let Some(i) = self.ready_until(Some(deadline)) {
+                return Some(i);
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+        }
+    }
+
+    fn ready_until(&mut self, deadline: Option<Instant>) -> Option<usize> {
+        assert!(
+            !self.handles.is_empty(),
+            "no operations have been added to `Select`"
+        );
+        if let Some(i) = self.try_ready() {
+            return Some(i);
+        }
+        let current = thread::current();
+        for handle in &self.handles {
+            handle.watch(&current);
+        }
+        // Re-check after registering so a message that raced with us isn't
+        // missed; if one arrives from here on, its unpark wakes us up.
+        let ready = self.try_ready();
+        if ready.is_none() {
+            match deadline {
+                Some(deadline) => {
+                    thread::park_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+                None => thread::park(),
+            }
+        }
+        for handle in &self.handles {
+            handle.unwatch(current.id());
+        }
+        ready
+    }
+}
+
+// Receives from several channels as one, without a forwarding thread per
+// input. Inputs are dropped as they disconnect, and the merge reports a
+// disconnect once all of them have.
+pub struct Merge<T> {
+    receivers: Vec<Receiver<T>>,
+    start: usize,
+}
+
+pub fn merge<T>(receivers: impl IntoIterator<Item = Receiver<T>>) -> Merge<T> {
+    Merge {
+        receivers: receivers.into_iter().collect(),
+        start: 0,
+    }
+}
+
+impl<T> Merge<T> {
+    pub fn recv(&mut self) -> Option<T> {
+        self.recv_until(None).ok()
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let n = self.receivers.len();
+        let mut disconnected = Vec::new();
+        // Start after the last input we took from so none of them starves.
+        for i in 0..n {
+            let index = (self.start + i) % n;
+            match self.receivers[index].try_recv() {
+                Ok(t) => {
+                    self.start = index + 1;
+                    return Ok(t);
+                }
+                Err(TryRecvError::Disconnected) => disconnected.push(index),
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+        // A disconnected receiver stays that way, so it can be dropped.
+        disconnected.sort_unstable();
+        for index in disconnected.into_iter().rev() {
+            self.receivers.remove(index);
+        }
+        if self.receivers.is_empty() {
+            Err(TryRecvError::Disconnected)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Instant::now().checked_add(timeout))
+    }
+
+    pub fn len(&self) -> usize {
+        self.receivers.iter().map(Receiver::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn recv_until(&mut self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        loop {
+            match self.try_recv() {
+                Ok(t) => return Ok(t),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => {}
+            }
+            let mut sel = Select::new();
+            for rx in &mut self.receivers {
+                sel.recv(rx);
+            }
+            match deadline {
+                None => {
+                    sel.ready();
+                }
+                Some(deadline) => {
+                    if sel.ready_deadline(deadline).is_none() {
+                        return Err(RecvTimeoutError::Timeout);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T> Iterator for Merge<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+#[macro_export]
+macro_rules! select {
+    ($(recv($rx:expr) -> $msg:pat => $body:expr),+ $(,)?) => {{
+        let index = {
+            let mut sel = $crate::Select::new();
+            $( sel.recv(&mut $rx); )+
+            sel.ready()
+        };
+        $crate::select!(@arm index, 0usize; $(recv($rx) -> $msg => $body,)+)
+    }};
+    (@arm $index:ident, $n:expr; recv($rx:expr) -> $msg:pat => $body:expr, $($rest:tt)*) => {
+        if $index == $n {
+            let $msg = $rx.try_recv().ok();
+            $body
+        } else {
+            $crate::select!(@arm $index, $n + 1usize; $($rest)*)
+        }
+    };
+    (@arm $index:ident, $n:expr;) => {
+        unreachable!()
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+    use crate::{channel, sync_channel};
+
+    #[test]
+    fn select_ready_test() {
+        let (tx1, mut rx1) = channel::<i32>();
+        let (tx2, mut rx2) = channel::<&str>();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            tx2.send("two").unwrap();
+            tx1
+        });
+        let mut sel = Select::new();
+        let i1 = sel.recv(&mut rx1);
+        let i2 = sel.recv(&mut rx2);
+        assert_eq!(sel.try_ready(), None);
+        assert_eq!(sel.ready(), i2);
+        assert_ne!(i1, i2);
+        assert_eq!(rx2.try_recv(), Ok("two"));
+        drop(handle.join().unwrap());
+        assert_eq!(rx1.try_recv(), Err(crate::TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn select_timeout_test() {
+        let (_tx, mut rx) = sync_channel::<i32>(0);
+        let mut sel = Select::new();
+        sel.recv(&mut rx);
+        assert_eq!(sel.ready_timeout(Duration::from_millis(10)), None);
+    }
+
+    #[test]
+    fn select_macro_test() {
+        let (tx1, mut rx1) = channel::<i32>();
+        let (tx2, mut rx2) = channel::<String>();
+        tx2.send("hi".to_string()).unwrap();
+        let got = crate::select! {
+            recv(rx1) -> msg => format!("{:?}", msg),
+            recv(rx2) -> msg => msg.unwrap(),
+        };
+        assert_eq!(got, "hi");
+        drop(tx1);
+        let got = crate::select! {
+            recv(rx1) -> msg => msg,
+            recv(rx2) -> _msg => unreachable!(),
+        };
+        assert_eq!(got, None);
+        drop(tx2);
+    }
+
+    #[test]
+    fn merge_test() {
+        let (tx1, rx1) = channel();
+        let (tx2, rx2) = channel();
+        let (tx3, rx3) = channel::<i32>();
+        drop(tx3);
+        let mut merged = merge([rx1, rx2, rx3]);
+        assert_eq!(merged.try_recv(), Err(TryRecvError::Empty));
+        let handle = thread::spawn(move || {
+            for i in 0..3 {
+                tx1.send(i).unwrap();
+                thread::sleep(Duration::from_millis(1));
+                tx2.send(i + 10).unwrap();
+            }
+        });
+        let mut got: Vec<_> = merged.by_ref().collect();
+        handle.join().unwrap();
+        got.sort();
+        assert_eq!(got, vec![0, 1, 2, 10, 11, 12]);
+        assert_eq!(
+            merged.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+}
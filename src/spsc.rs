@@ -0,0 +1,311 @@
+// A bounded single-producer, single-consumer channel over a pre-allocated
+// ring. Each side owns one index and only reads the other's, so the data
+// path is a couple of atomic loads and a store; a side that has to block
+// parks its thread and the other side unparks it.
+
+use std::{
+    cell::{Cell, UnsafeCell},
+    marker::PhantomData,
+    mem::MaybeUninit,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::{self, Thread},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    padded::CachePadded, sync::Mutex, RecvTimeoutError, SendError, TryRecvError, TrySendError,
+};
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+    // Only one thread may produce at a time.
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<T> Sender<T> {
+    pub fn send(&self, mut t: T) -> Result<(), SendError<T>> {
+        loop {
+            match self.try_send(t) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Full(back)) => t = back,
+                Err(e) => return Err(SendError::Disconnected(e.into_inner())),
+            }
+            let shared = &*self.shared;
+            shared.sender.prepare();
+            // Re-check now that the receiver can see we're about to park.
+            if shared.is_full() && shared.receiver_alive.load(Ordering::SeqCst) {
+                thread::park();
+            }
+        }
+    }
+
+    pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        let shared = &*self.shared;
+        if !shared.receiver_alive.load(Ordering::Relaxed) {
+            return Err(TrySendError::Disconnected(t));
+        }
+        // Only the sender moves the tail.
+        let tail = shared.tail.load(Ordering::Relaxed);
+        if tail - shared.head.load(Ordering::SeqCst) == shared.buffer.len() {
+            return Err(TrySendError::Full(t));
+        }
+        // SAFETY: the slot is outside the receiver's readable range until the
+        // tail is published.
+        unsafe {
+            shared.slot(tail).write(MaybeUninit::new(t));
+        }
+        shared.tail.store(tail + 1, Ordering::SeqCst);
+        shared.receiver.wake();
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.buffer.len()
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.shared.sender_alive.store(false, Ordering::SeqCst);
+        self.shared.receiver.wake();
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Receiver<T> {
+    pub fn recv(&mut self) -> Option<T> {
+        self.recv_until(None).ok()
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let shared = &*self.shared;
+        // Only the receiver moves the head.
+        let head = shared.head.load(Ordering::Relaxed);
+        if head == shared.tail.load(Ordering::SeqCst) {
+            if shared.sender_alive.load(Ordering::SeqCst) {
+                return Err(TryRecvError::Empty);
+            }
+            // The sender may have published a last message before dropping.
+            if head == shared.tail.load(Ordering::SeqCst) {
+                return Err(TryRecvError::Disconnected);
+            }
+        }
+        // SAFETY: the slot was published by the sender and won't be reused
+        // until the head moves past it.
+        let t = unsafe { shared.slot(head).read().assume_init() };
+        shared.head.store(head + 1, Ordering::SeqCst);
+        shared.sender.wake();
+        Ok(t)
+    }
+
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Instant::now().checked_add(timeout))
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.buffer.len()
+    }
+
+    fn recv_until(&mut self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        loop {
+            match self.try_recv() {
+                Ok(t) => return Ok(t),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => {}
+            }
+            let shared = &*self.shared;
+            shared.receiver.prepare();
+            // Re-check now that the sender can see we're about to park.
+            if !shared.is_empty() || !shared.sender_alive.load(Ordering::SeqCst) {
+                continue;
+            }
+            match deadline {
+                None => thread::park(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(RecvTimeoutError::Timeout);
+                    }
+                    thread::park_timeout(deadline - now);
+                }
+            }
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.receiver_alive.store(false, Ordering::SeqCst);
+        self.shared.sender.wake();
+    }
+}
+
+impl<T> Iterator for Receiver<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+struct Shared<T> {
+    buffer: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    // Monotonic; the slot is the index modulo the capacity.
+    head: CachePadded<AtomicUsize>,
+    tail: CachePadded<AtomicUsize>,
+    sender_alive: AtomicBool,
+    receiver_alive: AtomicBool,
+    sender: Waiter,
+    receiver: Waiter,
+}
+
+// SAFETY: each slot is accessed by one side at a time, handed over through
+// the head and tail indices.
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    fn slot(&self, index: usize) -> *mut MaybeUninit<T> {
+        self.buffer[index % self.buffer.len()].get()
+    }
+
+    fn len(&self) -> usize {
+        let head = self.head.load(Ordering::SeqCst);
+        self.tail.load(Ordering::SeqCst) - head
+    }
+
+    fn is_full(&self) -> bool {
+        self.len() == self.buffer.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let tail = *self.tail.get_mut();
+        for index in *self.head.get_mut()..tail {
+            // SAFETY: everything between head and tail was written and never
+            // read.
+            unsafe { (*self.slot(index)).assume_init_drop() };
+        }
+    }
+}
+
+// One side's parking spot. `waiting` is set while the thread is about to
+// park, so the other side only takes the lock when there is someone to wake.
+struct Waiter {
+    waiting: AtomicBool,
+    thread: Mutex<Option<Thread>>,
+}
+
+impl Waiter {
+    fn new() -> Self {
+        Waiter {
+            waiting: AtomicBool::new(false),
+            thread: Mutex::new(None),
+        }
+    }
+
+    // Handles can move between threads, so the current one is recorded on
+    // every wait.
+    fn prepare(&self) {
+        *self.thread.lock() = Some(thread::current());
+        self.waiting.store(true, Ordering::SeqCst);
+    }
+
+    fn wake(&self) {
+        if self.waiting.load(Ordering::SeqCst) && self.waiting.swap(false, Ordering::SeqCst) {
+            if let Some(thread) = &*self.thread.lock() {
+                thread.unpark();
+            }
+        }
+    }
+}
+
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "an spsc channel needs room for one message");
+    let shared = Shared {
+        buffer: (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+        head: CachePadded::new(AtomicUsize::new(0)),
+        tail: CachePadded::new(AtomicUsize::new(0)),
+        sender_alive: AtomicBool::new(true),
+        receiver_alive: AtomicBool::new(true),
+        sender: Waiter::new(),
+        receiver: Waiter::new(),
+    };
+
+    let shared = Arc::new(shared);
+
+    (
+        Sender {
+            shared: shared.clone(),
+            _not_sync: PhantomData,
+        },
+        Receiver { shared },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn spsc_test() {
+        let (tx, mut rx) = channel(2);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+        assert_eq!(rx.len(), 2);
+        assert_eq!(rx.try_recv(), Ok(1));
+        tx.send(3).unwrap();
+        drop(tx);
+        assert_eq!(rx.collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn spsc_threads_test() {
+        let (tx, mut rx) = channel(4);
+        let handle = thread::spawn(move || {
+            for i in 0..10_000 {
+                tx.send(i).unwrap();
+            }
+        });
+        for i in 0..10_000 {
+            assert_eq!(rx.recv(), Some(i));
+        }
+        handle.join().unwrap();
+        assert_eq!(
+            rx.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+        let (tx, rx) = channel::<i32>(1);
+        drop(rx);
+        assert_eq!(tx.send(1), Err(SendError::Disconnected(1)));
+    }
+}
@@ -0,0 +1,161 @@
+// The plumbing shared by the single-receiver channels that keep messages in
+// their own container (priority, lanes, conflate, delay): sender counting,
+// disconnection and the blocking receive loop. Each of them only supplies a
+// `Store` and wraps these handles in its own API.
+
+use std::time::Duration;
+
+use crate::{
+    sync::{Arc, Condvar, Instant, Mutex},
+    RecvTimeoutError, SendError, TryRecvError,
+};
+
+pub(crate) trait Store {
+    type Item;
+
+    fn len(&self) -> usize;
+
+    // Takes the next message that's ready. Otherwise returns when the next
+    // one will be, if a pending message is waiting on a deadline.
+    fn pop(&mut self) -> Result<Self::Item, Option<Instant>>;
+}
+
+pub(crate) struct Sender<S> {
+    shared: Arc<Shared<S>>,
+}
+
+impl<S> Clone for Sender<S> {
+    fn clone(&self) -> Self {
+        let mut inner = self.shared.inner.lock();
+        inner.senders += 1;
+        drop(inner);
+        Sender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<S> Sender<S> {
+    // Hands `m` to `put` to store, unless the receiver is gone. The receiver
+    // is woken either way, as it may be sleeping towards a later deadline.
+    pub(crate) fn send<M, R>(
+        &self,
+        m: M,
+        put: impl FnOnce(&mut S, M) -> R,
+    ) -> Result<R, SendError<M>> {
+        let mut inner = self.shared.inner.lock();
+        if !inner.receiver_alive {
+            return Err(SendError::Disconnected(m));
+        }
+        let r = put(&mut inner.store, m);
+        drop(inner);
+        self.shared.available.notify_one();
+        Ok(r)
+    }
+}
+
+impl<S> Drop for Sender<S> {
+    fn drop(&mut self) {
+        let mut inner = self.shared.inner.lock();
+        inner.senders -= 1;
+        let last = inner.senders == 0;
+        drop(inner);
+        if last {
+            self.shared.available.notify_one();
+        }
+    }
+}
+
+pub(crate) struct Receiver<S> {
+    shared: Arc<Shared<S>>,
+}
+
+impl<S: Store> Receiver<S> {
+    pub(crate) fn recv(&mut self) -> Option<S::Item> {
+        self.recv_until(None).ok()
+    }
+
+    pub(crate) fn try_recv(&mut self) -> Result<S::Item, TryRecvError> {
+        let mut inner = self.shared.inner.lock();
+        match inner.store.pop() {
+            Ok(t) => Ok(t),
+            Err(_) if inner.is_disconnected() => Err(TryRecvError::Disconnected),
+            Err(_) => Err(TryRecvError::Empty),
+        }
+    }
+
+    pub(crate) fn recv_timeout(&mut self, timeout: Duration) -> Result<S::Item, RecvTimeoutError> {
+        self.recv_until(Instant::now().checked_add(timeout))
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.shared.inner.lock().store.len()
+    }
+
+    fn recv_until(&mut self, deadline: Option<Instant>) -> Result<S::Item, RecvTimeoutError> {
+        let mut inner = self.shared.inner.lock();
+        loop {
+            let next = match inner.store.pop() {
+                Ok(t) => return Ok(t),
+                Err(_) if inner.is_disconnected() => return Err(RecvTimeoutError::Disconnected),
+                Err(next) => next,
+            };
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            let wake = match (next, deadline) {
+                (Some(next), Some(deadline)) => Some(next.min(deadline)),
+                (next, deadline) => next.or(deadline),
+            };
+            inner = crate::wait_until(&self.shared.available, inner, wake).0;
+        }
+    }
+}
+
+impl<S> Drop for Receiver<S> {
+    fn drop(&mut self) {
+        let mut inner = self.shared.inner.lock();
+        inner.receiver_alive = false;
+    }
+}
+
+struct Shared<S> {
+    inner: Mutex<Inner<S>>,
+    available: Condvar,
+}
+
+struct Inner<S> {
+    store: S,
+    senders: usize,
+    receiver_alive: bool,
+}
+
+impl<S: Store> Inner<S> {
+    // Messages still pending keep the channel going until they come due,
+    // even after the last sender is gone.
+    fn is_disconnected(&self) -> bool {
+        self.senders == 0 && self.store.len() == 0
+    }
+}
+
+pub(crate) fn channel<S>(store: S) -> (Sender<S>, Receiver<S>) {
+    let inner = Inner {
+        store,
+        senders: 1,
+        receiver_alive: true,
+    };
+
+    let shared = Shared {
+        inner: Mutex::new(inner),
+        available: Condvar::new(),
+    };
+
+    let shared = Arc::new(shared);
+
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
@@ -0,0 +1,401 @@
+// Lock primitives behind a common, non-poisoning interface: std's by default,
+// parking_lot's with the `parking_lot` feature, and a spin lock whose waits go
+// through a user-supplied `Blocker` without `std`. Under `cfg(loom)` the lock,
+// condvar and `Arc` come from loom so model tests can explore every
+// interleaving.
+//
+// Channel state is never left half-updated across a panic point; the only
+// code that can panic while the lock is held is a user callback such as a
+// dead letter handler or a `max_bytes` size estimate, and at worst that loses
+// the message being handled or skews the byte count. A panic on one thread
+// therefore leaves the channel usable for every other handle, and std locks
+// are simply recovered instead of propagating the poison.
+
+#[cfg(all(feature = "std", not(feature = "parking_lot"), not(loom)))]
+pub(crate) use self::std_impl::{Condvar, Mutex, MutexGuard};
+
+#[cfg(all(feature = "parking_lot", not(loom)))]
+pub(crate) use self::parking_lot_impl::{Condvar, Mutex, MutexGuard};
+
+#[cfg(loom)]
+pub(crate) use self::loom_impl::{Condvar, Mutex, MutexGuard};
+#[cfg(not(loom))]
+pub(crate) use alloc::sync::Arc;
+#[cfg(loom)]
+pub(crate) use loom::sync::Arc;
+
+// Trait objects go through std's `Arc`, as loom's can't be coerced to an
+// unsized type itself.
+#[cfg(not(loom))]
+pub(crate) fn arc_dyn<T: ?Sized>(arc: alloc::sync::Arc<T>) -> Arc<T> {
+    arc
+}
+#[cfg(loom)]
+pub(crate) fn arc_dyn<T: ?Sized>(arc: alloc::sync::Arc<T>) -> Arc<T> {
+    Arc::from_std(arc)
+}
+
+#[cfg(not(feature = "std"))]
+pub use self::spin_impl::{set_blocker, Blocker, Spin};
+#[cfg(not(feature = "std"))]
+pub(crate) use self::spin_impl::{Condvar, Instant, Mutex, MutexGuard};
+
+#[cfg(feature = "std")]
+pub(crate) use std::time::Instant;
+
+#[cfg(all(feature = "std", not(feature = "parking_lot"), not(loom)))]
+mod std_impl {
+    use std::{
+        sync::{self, PoisonError, TryLockError},
+        time::Duration,
+    };
+
+    pub(crate) use std::sync::MutexGuard;
+
+    pub(crate) struct Mutex<T>(sync::Mutex<T>);
+
+    impl<T> Mutex<T> {
+        pub(crate) const fn new(t: T) -> Self {
+            Mutex(sync::Mutex::new(t))
+        }
+
+        pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+            self.0.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        pub(crate) fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+            match self.0.try_lock() {
+                Ok(guard) => Some(guard),
+                Err(TryLockError::Poisoned(e)) => Some(e.into_inner()),
+                Err(TryLockError::WouldBlock) => None,
+            }
+        }
+    }
+
+    pub(crate) struct Condvar(sync::Condvar);
+
+    impl Condvar {
+        pub(crate) const fn new() -> Self {
+            Condvar(sync::Condvar::new())
+        }
+
+        pub(crate) fn wait<'a, T>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+            self.0.wait(guard).unwrap_or_else(PoisonError::into_inner)
+        }
+
+        // Callers re-check their own deadline, so the timeout flag isn't needed.
+        pub(crate) fn wait_timeout<'a, T>(
+            &self,
+            guard: MutexGuard<'a, T>,
+            timeout: Duration,
+        ) -> MutexGuard<'a, T> {
+            match self.0.wait_timeout(guard, timeout) {
+                Ok((guard, _)) => guard,
+                Err(e) => e.into_inner().0,
+            }
+        }
+
+        pub(crate) fn notify_one(&self) {
+            self.0.notify_one();
+        }
+
+        pub(crate) fn notify_all(&self) {
+            self.0.notify_all();
+        }
+    }
+}
+
+#[cfg(all(feature = "parking_lot", not(loom)))]
+mod parking_lot_impl {
+    use std::time::Duration;
+
+    pub(crate) use parking_lot::MutexGuard;
+
+    pub(crate) struct Mutex<T>(parking_lot::Mutex<T>);
+
+    impl<T> Mutex<T> {
+        pub(crate) const fn new(t: T) -> Self {
+            Mutex(parking_lot::Mutex::new(t))
+        }
+
+        pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+            self.0.lock()
+        }
+
+        pub(crate) fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+            self.0.try_lock()
+        }
+    }
+
+    pub(crate) struct Condvar(parking_lot::Condvar);
+
+    impl Condvar {
+        pub(crate) const fn new() -> Self {
+            Condvar(parking_lot::Condvar::new())
+        }
+
+        pub(crate) fn wait<'a, T>(&self, mut guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+            self.0.wait(&mut guard);
+            guard
+        }
+
+        pub(crate) fn wait_timeout<'a, T>(
+            &self,
+            mut guard: MutexGuard<'a, T>,
+            timeout: Duration,
+        ) -> MutexGuard<'a, T> {
+            self.0.wait_for(&mut guard, timeout);
+            guard
+        }
+
+        pub(crate) fn notify_one(&self) {
+            self.0.notify_one();
+        }
+
+        pub(crate) fn notify_all(&self) {
+            self.0.notify_all();
+        }
+    }
+}
+
+#[cfg(loom)]
+mod loom_impl {
+    use loom::sync;
+    use std::{
+        sync::{PoisonError, TryLockError},
+        time::Duration,
+    };
+
+    pub(crate) use loom::sync::MutexGuard;
+
+    // Not const: loom objects must be created inside a model.
+    pub(crate) struct Mutex<T>(sync::Mutex<T>);
+
+    impl<T> Mutex<T> {
+        pub(crate) fn new(t: T) -> Self {
+            Mutex(sync::Mutex::new(t))
+        }
+
+        pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+            self.0.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        pub(crate) fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+            match self.0.try_lock() {
+                Ok(guard) => Some(guard),
+                Err(TryLockError::Poisoned(e)) => Some(e.into_inner()),
+                Err(TryLockError::WouldBlock) => None,
+            }
+        }
+    }
+
+    pub(crate) struct Condvar(sync::Condvar);
+
+    impl Condvar {
+        pub(crate) fn new() -> Self {
+            Condvar(sync::Condvar::new())
+        }
+
+        pub(crate) fn wait<'a, T>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+            self.0.wait(guard).unwrap_or_else(PoisonError::into_inner)
+        }
+
+        pub(crate) fn wait_timeout<'a, T>(
+            &self,
+            guard: MutexGuard<'a, T>,
+            timeout: Duration,
+        ) -> MutexGuard<'a, T> {
+            match self.0.wait_timeout(guard, timeout) {
+                Ok((guard, _)) => guard,
+                Err(e) => e.into_inner().0,
+            }
+        }
+
+        pub(crate) fn notify_one(&self) {
+            self.0.notify_one();
+        }
+
+        pub(crate) fn notify_all(&self) {
+            self.0.notify_all();
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+mod spin_impl {
+    use core::{
+        cell::UnsafeCell,
+        hint,
+        ops::{Deref, DerefMut},
+        sync::atomic::{AtomicBool, AtomicU32, Ordering},
+        time::Duration,
+    };
+
+    // Puts the current thread of execution to sleep until another one calls
+    // `wake` on the same word. Like a futex, `wait` must return promptly if
+    // `word` no longer holds `expected`, which is what makes a wake sent just
+    // before the sleep impossible to miss; returning early is always allowed.
+    pub trait Blocker: Sync {
+        fn wait(&self, word: &AtomicU32, expected: u32);
+        fn wake(&self, word: &AtomicU32);
+    }
+
+    // Busy-waits; the default until `set_blocker` installs something better.
+    pub struct Spin;
+
+    impl Blocker for Spin {
+        fn wait(&self, word: &AtomicU32, expected: u32) {
+            while word.load(Ordering::Acquire) == expected {
+                hint::spin_loop();
+            }
+        }
+
+        fn wake(&self, _word: &AtomicU32) {}
+    }
+
+    static BLOCKER: Mutex<&'static dyn Blocker> = Mutex::new(&Spin);
+
+    // Applies to every channel; meant to be called once during start-up.
+    pub fn set_blocker(blocker: &'static dyn Blocker) {
+        *BLOCKER.lock() = blocker;
+    }
+
+    fn blocker() -> &'static dyn Blocker {
+        *BLOCKER.lock()
+    }
+
+    // There is no clock without `std`, so nothing can be stamped or given a
+    // deadline.
+    #[derive(Clone, Copy)]
+    pub(crate) enum Instant {}
+
+    impl Instant {
+        pub(crate) fn elapsed(&self) -> Duration {
+            match *self {}
+        }
+    }
+
+    pub(crate) struct Mutex<T> {
+        locked: AtomicBool,
+        value: UnsafeCell<T>,
+    }
+
+    unsafe impl<T: Send> Send for Mutex<T> {}
+    unsafe impl<T: Send> Sync for Mutex<T> {}
+
+    impl<T> Mutex<T> {
+        pub(crate) const fn new(t: T) -> Self {
+            Mutex {
+                locked: AtomicBool::new(false),
+                value: UnsafeCell::new(t),
+            }
+        }
+
+        pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+            loop {
+                if let Some(guard) = self.try_lock() {
+                    return guard;
+                }
+                while self.locked.load(Ordering::Relaxed) {
+                    hint::spin_loop();
+                }
+            }
+        }
+
+        pub(crate) fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+            self.locked
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .ok()
+                .map(|_| MutexGuard { mutex: self })
+        }
+    }
+
+    pub(crate) struct MutexGuard<'a, T> {
+        mutex: &'a Mutex<T>,
+    }
+
+    impl<T> Deref for MutexGuard<'_, T> {
+        type Target = T;
+        fn deref(&self) -> &T {
+            // SAFETY: holding the guard means holding the lock.
+            unsafe { &*self.mutex.value.get() }
+        }
+    }
+
+    impl<T> DerefMut for MutexGuard<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            // SAFETY: holding the guard means holding the lock.
+            unsafe { &mut *self.mutex.value.get() }
+        }
+    }
+
+    impl<T> Drop for MutexGuard<'_, T> {
+        fn drop(&mut self) {
+            self.mutex.locked.store(false, Ordering::Release);
+        }
+    }
+
+    // Every notification bumps `seq`; a waiter reads it before releasing the
+    // lock and sleeps only while it is unchanged.
+    pub(crate) struct Condvar {
+        seq: AtomicU32,
+    }
+
+    impl Condvar {
+        pub(crate) const fn new() -> Self {
+            Condvar {
+                seq: AtomicU32::new(0),
+            }
+        }
+
+        pub(crate) fn wait<'a, T>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+            let seq = self.seq.load(Ordering::Acquire);
+            let mutex = guard.mutex;
+            drop(guard);
+            blocker().wait(&self.seq, seq);
+            mutex.lock()
+        }
+
+        pub(crate) fn notify_one(&self) {
+            self.notify_all();
+        }
+
+        pub(crate) fn notify_all(&self) {
+            self.seq.fetch_add(1, Ordering::Release);
+            blocker().wake(&self.seq);
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "std")))]
+mod test {
+    use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+    use std::thread;
+
+    use super::*;
+    use crate::channel;
+
+    struct Counting(AtomicUsize);
+
+    impl Blocker for Counting {
+        fn wait(&self, word: &AtomicU32, expected: u32) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Spin.wait(word, expected);
+        }
+
+        fn wake(&self, _word: &AtomicU32) {}
+    }
+
+    #[test]
+    fn blocker_test() {
+        static BLOCKER: Counting = Counting(AtomicUsize::new(0));
+        set_blocker(&BLOCKER);
+        let (tx, mut rx) = channel();
+        let handle = thread::spawn(move || rx.recv());
+        while BLOCKER.0.load(Ordering::SeqCst) == 0 {
+            thread::yield_now();
+        }
+        tx.send(1).unwrap();
+        assert_eq!(handle.join().unwrap(), Some(1));
+    }
+}
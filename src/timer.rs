@@ -0,0 +1,209 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    sync::OnceLock,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    sync::{Condvar, Mutex},
+    sync_channel, Receiver, Sender, TrySendError,
+};
+
+pub fn after(duration: Duration) -> Receiver<Instant> {
+    schedule(duration, None)
+}
+
+// A slow receiver sees at most one pending tick; ticks that would have
+// queued behind it are skipped.
+pub fn tick(period: Duration) -> Receiver<Instant> {
+    assert!(!period.is_zero(), "tick period must be non-zero");
+    schedule(period, Some(period))
+}
+
+fn schedule(delay: Duration, period: Option<Duration>) -> Receiver<Instant> {
+    let (tx, rx) = sync_channel(1);
+    let timer = timer();
+    let mut inner = timer.inner.lock();
+    let Some(at) = Instant::now().checked_add(delay) else {
+        inner.park(tx);
+        return rx;
+    };
+    let seq = inner.seq;
+    inner.seq += 1;
+    inner.heap.push(Entry {
+        at,
+        seq,
+        sender: tx,
+        period,
+    });
+    drop(inner);
+    timer.changed.notify_one();
+    rx
+}
+
+fn timer() -> &'static Timer {
+    static TIMER: OnceLock<Timer> = OnceLock::new();
+    TIMER.get_or_init(|| {
+        thread::Builder::new()
+            .name("channel-timer".into())
+            .spawn(|| timer().run())
+            .expect("failed to spawn timer thread");
+        Timer {
+            inner: Mutex::new(TimerInner {
+                heap: BinaryHeap::new(),
+                seq: 0,
+                never: Vec::new(),
+            }),
+            changed: Condvar::new(),
+        }
+    })
+}
+
+struct Timer {
+    inner: Mutex<TimerInner>,
+    changed: Condvar,
+}
+
+impl Timer {
+    fn run(&self) {
+        let mut inner = self.inner.lock();
+        loop {
+            let now = Instant::now();
+            let next = match inner.heap.peek() {
+                Some(entry) if entry.at <= now => inner.heap.pop().unwrap(),
+                Some(entry) => {
+                    let wait = entry.at - now;
+                    inner = self.changed.wait_timeout(inner, wait);
+                    continue;
+                }
+                None => {
+                    inner = self.changed.wait(inner);
+                    continue;
+                }
+            };
+            let Entry {
+                at,
+                seq,
+                sender,
+                period,
+            } = next;
+            match sender.try_send(at) {
+                Err(TrySendError::Disconnected(_) | TrySendError::Closed(_)) => continue,
+                Ok(()) | Err(TrySendError::Full(_)) => {}
+            }
+            if let Some(step) = period {
+                // Don't try to catch up on ticks missed while we were late.
+                let mut next = at.checked_add(step);
+                while let Some(at) = next.filter(|&at| at <= now) {
+                    next = at.checked_add(step);
+                }
+                match next {
+                    Some(at) => inner.heap.push(Entry {
+                        at,
+                        seq,
+                        sender,
+                        period,
+                    }),
+                    None => inner.park(sender),
+                }
+            }
+        }
+    }
+}
+
+struct TimerInner {
+    heap: BinaryHeap<Entry>,
+    seq: u64,
+    // Timers too far off for an `Instant` to represent. They never fire, but
+    // keep their receivers from seeing a disconnect.
+    never: Vec<Sender<Instant>>,
+}
+
+impl TimerInner {
+    fn park(&mut self, sender: Sender<Instant>) {
+        self.never.retain(|sender| !sender.is_closed());
+        self.never.push(sender);
+    }
+}
+
+struct Entry {
+    at: Instant,
+    seq: u64,
+    sender: Sender<Instant>,
+    period: Option<Duration>,
+}
+
+// Reversed so the heap yields the earliest deadline first.
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.at, other.seq).cmp(&(self.at, self.seq))
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::channel;
+
+    #[test]
+    fn after_test() {
+        let start = Instant::now();
+        let mut rx = after(Duration::from_millis(20));
+        let fired = rx.recv().unwrap();
+        assert!(fired >= start + Duration::from_millis(20));
+        assert!(Instant::now() >= fired);
+        assert_eq!(rx.recv(), None);
+    }
+
+    #[test]
+    fn tick_test() {
+        let start = Instant::now();
+        let mut rx = tick(Duration::from_millis(10));
+        let ticks: Vec<_> = (0..3).map(|_| rx.recv().unwrap()).collect();
+        for (i, at) in ticks.iter().enumerate() {
+            assert!(*at >= start + Duration::from_millis(10) * (i as u32 + 1));
+        }
+        assert!(ticks.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn far_future_test() {
+        let mut never = after(Duration::MAX);
+        let mut soon = after(Duration::from_millis(1));
+        assert!(soon.recv().is_some());
+        assert_eq!(never.try_recv(), Err(crate::TryRecvError::Empty));
+    }
+
+    #[test]
+    #[should_panic(expected = "tick period must be non-zero")]
+    fn zero_tick_test() {
+        tick(Duration::ZERO);
+    }
+
+    #[test]
+    fn select_timeout_test() {
+        let (_tx, mut rx) = channel::<i32>();
+        let mut timeout = after(Duration::from_millis(10));
+        let timed_out = crate::select! {
+            recv(rx) -> _msg => false,
+            recv(timeout) -> _at => true,
+        };
+        assert!(timed_out);
+    }
+}
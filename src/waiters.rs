@@ -0,0 +1,107 @@
+// Everyone waiting on one side of a channel other than through its condvar:
+// threads parked in a `Select` and async tasks. Keeping both in one list
+// means each state change wakes each waiter exactly once, whatever kind it is.
+
+use alloc::vec::Vec;
+use core::task::Waker;
+#[cfg(feature = "std")]
+use std::thread::{Thread, ThreadId};
+
+enum Waiter {
+    // Stays registered until it unwatches.
+    #[cfg(feature = "std")]
+    Thread(Thread),
+    // Woken once, then forgotten.
+    Task(Waker),
+}
+
+pub(crate) struct WaitList {
+    waiters: Vec<Waiter>,
+}
+
+impl WaitList {
+    pub(crate) const fn new() -> Self {
+        WaitList {
+            waiters: Vec::new(),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn watch(&mut self, thread: &Thread) {
+        self.waiters.push(Waiter::Thread(thread.clone()));
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn unwatch(&mut self, id: ThreadId) {
+        self.waiters
+            .retain(|w| !matches!(w, Waiter::Thread(t) if t.id() == id));
+    }
+
+    pub(crate) fn register(&mut self, waker: &Waker) {
+        let known = self
+            .waiters
+            .iter()
+            .any(|w| matches!(w, Waiter::Task(w) if w.will_wake(waker)));
+        if !known {
+            self.waiters.push(Waiter::Task(waker.clone()));
+        }
+    }
+
+    pub(crate) fn unregister(&mut self, waker: &Waker) {
+        self.waiters
+            .retain(|w| !matches!(w, Waiter::Task(w) if w.will_wake(waker)));
+    }
+
+    pub(crate) fn wake_all(&mut self) {
+        self.waiters.retain(|w| match w {
+            #[cfg(feature = "std")]
+            Waiter::Thread(thread) => {
+                thread.unpark();
+                true
+            }
+            Waiter::Task(waker) => {
+                waker.wake_by_ref();
+                false
+            }
+        });
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::Wake,
+        thread,
+    };
+
+    use super::*;
+
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn wait_list_test() {
+        let count = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(Arc::clone(&count));
+        let mut list = WaitList::new();
+        list.watch(&thread::current());
+        list.register(&waker);
+        list.register(&waker);
+        list.wake_all();
+        list.wake_all();
+        // The task was registered once and woken once; the thread stays.
+        assert_eq!(count.0.load(Ordering::Relaxed), 1);
+        assert_eq!(list.waiters.len(), 1);
+        list.unwatch(thread::current().id());
+        assert!(list.waiters.is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+use std::{error::Error, fmt, ops::Deref};
+
+use crate::{
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    SendError,
+};
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        let mut inner = self.shared.inner.lock();
+        if inner.receivers == 0 {
+            return Err(SendError::Disconnected(t));
+        }
+        inner.value = t;
+        inner.version += 1;
+        drop(inner);
+        self.shared.changed.notify_all();
+        Ok(())
+    }
+
+    pub fn send_replace(&self, t: T) -> T {
+        let mut inner = self.shared.inner.lock();
+        let old = std::mem::replace(&mut inner.value, t);
+        inner.version += 1;
+        drop(inner);
+        self.shared.changed.notify_all();
+        old
+    }
+
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref {
+            inner: self.shared.inner.lock(),
+        }
+    }
+
+    pub fn subscribe(&self) -> Receiver<T> {
+        let mut inner = self.shared.inner.lock();
+        inner.receivers += 1;
+        let seen = inner.version;
+        drop(inner);
+        Receiver {
+            shared: Arc::clone(&self.shared),
+            seen,
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut inner = self.shared.inner.lock();
+        inner.closed = true;
+        drop(inner);
+        self.shared.changed.notify_all();
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    seen: u64,
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        let mut inner = self.shared.inner.lock();
+        inner.receivers += 1;
+        drop(inner);
+        Receiver {
+            shared: Arc::clone(&self.shared),
+            seen: self.seen,
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    // The returned guard holds the channel lock, so keep it short-lived;
+    // the sender blocks until it is dropped.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref {
+            inner: self.shared.inner.lock(),
+        }
+    }
+
+    pub fn borrow_and_update(&mut self) -> Ref<'_, T> {
+        let inner = self.shared.inner.lock();
+        self.seen = inner.version;
+        Ref { inner }
+    }
+
+    pub fn has_changed(&self) -> bool {
+        self.shared.inner.lock().version != self.seen
+    }
+
+    pub fn changed(&mut self) -> Result<(), RecvError> {
+        let mut inner = self.shared.inner.lock();
+        loop {
+            if inner.version != self.seen {
+                self.seen = inner.version;
+                return Ok(());
+            }
+            if inner.closed {
+                return Err(RecvError);
+            }
+            inner = self.shared.changed.wait(inner);
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut inner = self.shared.inner.lock();
+        inner.receivers -= 1;
+    }
+}
+
+pub struct Ref<'a, T> {
+    inner: MutexGuard<'a, Inner<T>>,
+}
+
+impl<T> Deref for Ref<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.inner.value
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "watch sender was dropped".fmt(f)
+    }
+}
+
+impl Error for RecvError {}
+
+struct Shared<T> {
+    inner: Mutex<Inner<T>>,
+    changed: Condvar,
+}
+
+struct Inner<T> {
+    value: T,
+    version: u64,
+    receivers: usize,
+    closed: bool,
+}
+
+pub fn channel<T>(initial: T) -> (Sender<T>, Receiver<T>) {
+    let inner = Inner {
+        value: initial,
+        version: 0,
+        receivers: 1,
+        closed: false,
+    };
+
+    let shared = Shared {
+        inner: Mutex::new(inner),
+        changed: Condvar::new(),
+    };
+
+    let shared = Arc::new(shared);
+
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared, seen: 0 },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::{thread, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn latest_value_test() {
+        let (tx, mut rx) = channel("a");
+        assert_eq!(*rx.borrow(), "a");
+        assert!(!rx.has_changed());
+        tx.send("b").unwrap();
+        tx.send("c").unwrap();
+        assert!(rx.has_changed());
+        assert_eq!(*rx.borrow_and_update(), "c");
+        assert!(!rx.has_changed());
+        drop(tx);
+        assert_eq!(rx.changed(), Err(RecvError));
+        assert_eq!(*rx.borrow(), "c");
+    }
+
+    #[test]
+    fn changed_blocks_test() {
+        let (tx, mut rx) = channel(0);
+        let handle = thread::spawn(move || {
+            rx.changed().unwrap();
+            *rx.borrow()
+        });
+        thread::sleep(Duration::from_millis(10));
+        tx.send(5).unwrap();
+        assert_eq!(handle.join().unwrap(), 5);
+        assert_eq!(tx.send(6), Err(SendError::Disconnected(6)));
+        assert_eq!(tx.send_replace(7), 5);
+        assert_eq!(*tx.borrow(), 7);
+    }
+}